@@ -0,0 +1,145 @@
+use crate::env::Environment;
+use crate::ir::{bridge::Bridged, Origin, Value};
+use crate::parser::FileSource;
+use std::collections::HashMap;
+use std::fmt;
+use termion::color;
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub fault: RuntimeFault,
+    // The builtin that failed along with the values it was given
+    pub builtin: Option<(Bridged, Vec<Value>)>,
+    // The calls that were being evaluated, innermost first
+    pub call_path: Vec<Frame>,
+}
+
+#[derive(Debug)]
+pub struct Frame {
+    pub origin: Origin,
+    // Filled in by `load_source_code`, functions from the prelude don't have one
+    pub line: Option<usize>,
+    // Tail calls dropped between this function and its caller
+    pub elided: usize,
+}
+
+#[derive(Debug)]
+pub enum RuntimeFault {
+    DivisionByZero,
+    IntegerOverflow,
+    // A builtin was given values it can't handle
+    UnexpectedValues,
+    IndexOutOfBounds(i64, usize),
+    NoMatchingArm(Value),
+    Unimplemented,
+}
+
+impl RuntimeFault {
+    pub fn in_builtin(self, func: Bridged, params: &[Value]) -> RuntimeError {
+        RuntimeError {
+            fault: self,
+            builtin: Some((func, params.to_vec())),
+            call_path: Vec::new(),
+        }
+    }
+}
+
+impl RuntimeError {
+    pub fn load_source_code(mut self, env: &Environment) -> Self {
+        let mut sources: HashMap<FileSource, Option<String>> = HashMap::new();
+        for frame in self.call_path.iter_mut() {
+            let module = &frame.origin.module;
+            if *module == FileSource::Prelude {
+                continue;
+            }
+            let source = sources
+                .entry(module.clone())
+                .or_insert_with(|| std::fs::read_to_string(module.to_pathbuf(env)).ok());
+            frame.line = source.as_ref().map(|source| {
+                source
+                    .chars()
+                    .take(frame.origin.source_index)
+                    .filter(|c| *c == '\n')
+                    .count()
+                    + 1
+            });
+        }
+        self
+    }
+}
+
+impl From<RuntimeFault> for RuntimeError {
+    fn from(fault: RuntimeFault) -> Self {
+        Self {
+            fault,
+            builtin: None,
+            call_path: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{g}leaf{n} runtime{g}:{n}",
+            g = color::Green.fg_str(),
+            n = color::Reset.fg_str(),
+        )?;
+
+        use RuntimeFault::*;
+        match &self.fault {
+            DivisionByZero => write!(f, "Division by zero")?,
+            IntegerOverflow => write!(f, "The result doesn't fit in an `int`")?,
+            UnexpectedValues => write!(f, "This builtin can't be used with these values")?,
+            IndexOutOfBounds(i, len) => write!(
+                f,
+                "Index {} is out of bounds for a list of length {}",
+                i, len
+            )?,
+            NoMatchingArm(v) => write!(
+                f,
+                "None of the arms of this match expression matched the value {}",
+                v
+            )?,
+            Unimplemented => write!(f, "Reached a `???` that hasn't been implemented yet")?,
+        }
+
+        if let Some((func, params)) = &self.builtin {
+            write!(
+                f,
+                "\n {y}in{n} builtin:{}",
+                func.as_ref(),
+                y = color::Yellow.fg_str(),
+                n = color::Reset.fg_str(),
+            )?;
+            for p in params.iter() {
+                write!(f, " {}", p)?;
+            }
+        }
+        for frame in self.call_path.iter() {
+            write!(
+                f,
+                "\n {y}in{n} {} ({}",
+                frame.origin,
+                frame.origin.module,
+                y = color::Yellow.fg_str(),
+                n = color::Reset.fg_str(),
+            )?;
+            if let Some(line) = frame.line {
+                write!(f, " line {}", line)?;
+            }
+            write!(f, ")")?;
+            if frame.elided != 0 {
+                write!(
+                    f,
+                    "\n {y}...{n} {} tail call(s) elided",
+                    frame.elided,
+                    y = color::Yellow.fg_str(),
+                    n = color::Reset.fg_str(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
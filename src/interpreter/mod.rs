@@ -1,3 +1,5 @@
+mod error;
+pub use error::{Frame, RuntimeError, RuntimeFault};
 mod runner;
 pub use runner::Runner;
 mod runtime;
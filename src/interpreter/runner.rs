@@ -1,39 +1,93 @@
 use super::runtime::Runtime;
-use crate::ir::{bridge::Bridged, Capturable, Entity, First, If, Value};
+use super::{Frame, RuntimeError, RuntimeFault};
+use crate::ir::{bridge::Bridged, Capturable, Entity, If, Match, Pattern, Value};
+use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::rc::Rc;
 
 mod bridge;
+use bridge::Mapping;
 mod parambuffer;
 use parambuffer::*;
 use termion::color::{Fg, Green, Reset, Yellow};
 
+// Evaluation never recurses on the native stack. Whatever is left to do is kept as a stack of
+// tasks, and evaluated entities push their values for the task that's waiting on them. So how
+// deeply leaf code can recurse is only limited by memory.
 pub struct Runner<'a> {
     runtime: &'a Runtime,
-    entity: &'a Entity,
-    params: ParamBuffer<'a>,
-    captured: Vec<Value>,
+    tasks: Vec<Task<'a>>,
+    values: Vec<Value>,
+}
+
+// The parameters and captured values a part of a function body is evaluated with
+struct Env {
+    params: ParamBuffer,
+    captured: Rc<Vec<Value>>,
+    // Memoized values, shared by everything evaluated within the same call
+    memoized: Rc<RefCell<Vec<Option<Value>>>>,
+}
+
+enum Task<'a> {
+    Eval(&'a Entity, Rc<Env>),
+    Push(Value),
+    // Where a function was entered and the height of the value stack at that point. A failed `try`
+    // unwinds to here. Lambdas that are called directly don't have a function index of their own.
+    // The function index is replaced by tail calls, so the last one it replaced is kept for
+    // backtraces.
+    Return(Option<u32>, Option<TailCall>, usize),
+
+    // The rest take their inputs from the top of the value stack
+    Call(u32, usize),
+    CallClosure(u32, Rc<Vec<Value>>, usize),
+    // Body, amount of captures and amount of parameters
+    CallLambda(&'a Entity, usize, usize),
+    MakeClosure(u32, usize),
+    RustCall(Bridged, usize),
+    If(&'a If<Entity>, usize, Rc<Env>),
+    Match(&'a Match<Entity>, Rc<Env>),
+    Let(&'a Entity, Rc<Env>),
+    // Remembers the value on top of the stack without consuming it
+    Memoize(u16, Rc<Env>),
+    And(&'a Entity, Rc<Env>),
+    Or(&'a Entity, Rc<Env>),
+    Discard,
+    List(usize),
+    Record(usize),
+    Tuple(usize),
+    UpdateRecord(&'a [(u16, Entity)]),
+    FieldAccess(u16),
+    ConstructEnum(u16, &'a Rc<str>, usize),
+    Try(u16),
+    Exit,
+    Map(Box<Mapping>),
+}
+
+// The function that made the latest tail call into a frame, and how many tail calls before it
+// were dropped
+struct TailCall {
+    caller: u32,
+    elided: usize,
 }
 
 #[allow(unused)]
-fn debug_dump_entity(runner: &Runner) {
-    match runner.entity {
+fn debug_dump_entity(entity: &Entity, env: &Env) {
+    match entity {
         Entity::Parameter(_) | Entity::Captured(_) | Entity::Inlined(_) | Entity::List(_) => {}
         _ => println!(
             " {g}runner{r} {y}->{r} {g}using{r} ({y}|{r}{}{y}|{r}) {y}({r}{}{y}){r} {g}evaluating{r} {}",
-            runner
-                .captured
+            env.captured
                 .iter()
                 .map(|c| c.to_string())
                 .collect::<Vec<_>>()
                 .join(" "),
-            runner
-                .params
+            env.params
                 .as_slice()
                 .iter()
                 .map(|p| p.to_string())
                 .collect::<Vec<_>>()
                 .join(" "),
-            runner.entity,
+            entity,
             r = Fg(Reset),
             g = Fg(Green),
             y = Fg(Yellow)
@@ -42,153 +96,461 @@ fn debug_dump_entity(runner: &Runner) {
 }
 
 impl<'a> Runner<'a> {
-    pub fn start(runtime: &'a Runtime, entrypoint: &'a Entity, mut params: Vec<Value>) -> Value {
-        Self {
+    pub fn start(
+        runtime: &'a Runtime,
+        entrypoint: u32,
+        params: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let env = Env {
+            params: ParamBuffer::from(params.into_iter()),
+            captured: Rc::default(),
+            memoized: Rc::default(),
+        };
+        let body = &runtime.instructions[entrypoint as usize];
+        let mut runner = Self {
             runtime,
-            entity: entrypoint,
-            params: ParamBuffer::from(params.drain(0..)),
-            captured: Vec::new(),
-        }
-        .run()
+            tasks: vec![
+                Task::Return(Some(entrypoint), None, 0),
+                Task::Eval(body, Rc::new(env)),
+            ],
+            values: Vec::new(),
+        };
+        runner.run()?;
+        Ok(runner.values.pop().unwrap())
     }
 
-    fn spawn(&self, entity: &'a Entity, params: ParamBuffer, captured: Vec<Value>) -> Value {
-        Runner {
-            runtime: self.runtime,
-            entity,
-            params,
-            captured,
+    fn run(&mut self) -> Result<(), RuntimeError> {
+        while let Some(task) = self.tasks.pop() {
+            if let Err(mut e) = self.step(task) {
+                e.call_path = self.call_path();
+                return Err(e);
+            }
         }
-        .run()
+        Ok(())
     }
 
-    fn run(mut self) -> Value {
-        #[cfg(debug_assertions)]
-        debug_dump_entity(&self);
-
-        loop {
-            match self.entity {
-                Entity::RustCall(index, params) => return self.rust_call(*index, params),
-                Entity::Parameter(n) => return self.params.clone_param(*n as usize),
-                Entity::Inlined(v) => return v.clone(),
-                Entity::IfExpression(expr) => return self.if_expression(expr),
-                Entity::FirstStatement(stmt) => return self.first_statement(stmt),
-                Entity::List(list) => return self.list(list),
-                Entity::ParameterCall(paramid, params) => {
-                    let evaluated_params = self.eval_params(params);
-                    if let Value::Function(box (entity, captured)) =
-                        self.params.clone_param(*paramid as usize)
-                    {
-                        // TODO: Fix memory management
-                        return self.spawn(&entity, evaluated_params, captured);
-                    } else {
-                        unreachable!();
-                    }
+    // The functions currently being evaluated, innermost first
+    fn call_path(&self) -> Vec<Frame> {
+        self.tasks
+            .iter()
+            .rev()
+            .flat_map(|task| match task {
+                Task::Return(findex, tail, _) => {
+                    let current = findex.map(|findex| self.frame(findex, 0));
+                    let tail = tail
+                        .as_ref()
+                        .map(|tail| self.frame(tail.caller, tail.elided));
+                    current.into_iter().chain(tail).collect()
                 }
-                Entity::CapturedCall(capid, params) => {
-                    let evaluated_params = self.eval_params(params);
-                    if let Value::Function(box (entity, captured)) =
-                        self.captured[*capid as usize].clone()
-                    {
-                        // TODO: Fix memory management
-                        return self.spawn(&entity, evaluated_params, captured);
-                    } else {
-                        unreachable!();
-                    }
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    fn frame(&self, findex: u32, elided: usize) -> Frame {
+        Frame {
+            origin: self.runtime.origins[findex as usize].clone(),
+            line: None,
+            elided,
+        }
+    }
+
+    fn step(&mut self, task: Task<'a>) -> Result<(), RuntimeError> {
+        match task {
+            Task::Eval(entity, env) => self.eval(entity, env)?,
+            Task::Return(_, _, _) => {}
+            Task::Push(v) => self.values.push(v),
+            Task::Call(findex, arity) => {
+                let runtime = self.runtime;
+                let params = self.pop_params(arity);
+                let env = Env {
+                    params,
+                    captured: Rc::default(),
+                    memoized: Rc::default(),
+                };
+                self.enter(Some(findex), &runtime.instructions[findex as usize], env);
+            }
+            Task::CallClosure(findex, captured, arity) => {
+                let runtime = self.runtime;
+                let params = self.pop_params(arity);
+                self.enter(
+                    Some(findex),
+                    &runtime.instructions[findex as usize],
+                    Env {
+                        params,
+                        captured,
+                        memoized: Rc::default(),
+                    },
+                );
+            }
+            Task::CallLambda(body, captures, arity) => {
+                let params = self.pop_params(arity);
+                let captured = self.pop_values(captures);
+                self.enter(
+                    None,
+                    body,
+                    Env {
+                        params,
+                        captured: Rc::new(captured),
+                        memoized: Rc::default(),
+                    },
+                );
+            }
+            Task::MakeClosure(findex, captures) => {
+                let captured = self.pop_values(captures);
+                self.values
+                    .push(Value::Function(Box::new((findex, captured))));
+            }
+            Task::RustCall(index, arity) => {
+                let params = self.pop_params(arity);
+                self.eval_bridged(index, params)?;
+            }
+            Task::If(expr, i, env) => match self.values.pop() {
+                Some(Value::Bool(true)) => self.tasks.push(Task::Eval(expr.evaluation(i), env)),
+                _ => self.branch(expr, i + 1, env),
+            },
+            Task::Match(expr, env) => {
+                let value = self.values.pop().unwrap();
+                let mut bound = Vec::new();
+                let (_, eval) = match expr.arms().iter().find(|(pattern, _)| {
+                    bound.clear();
+                    matches(pattern, &value, &mut bound)
+                }) {
+                    Some(arm) => arm,
+                    None => return Err(RuntimeFault::NoMatchingArm(value).into()),
+                };
+                let env = if bound.is_empty() {
+                    env
+                } else {
+                    Rc::new(env.extended(bound.drain(0..)))
+                };
+                self.tasks.push(Task::Eval(eval, env));
+            }
+            Task::Let(body, env) => {
+                let value = self.values.pop().unwrap();
+                let env = Rc::new(env.extended(std::iter::once(value)));
+                self.tasks.push(Task::Eval(body, env));
+            }
+            Task::Memoize(slot, env) => {
+                let value = self.values.last().unwrap().clone();
+                let mut memoized = env.memoized.borrow_mut();
+                let slot = slot as usize;
+                if memoized.len() <= slot {
+                    memoized.resize(slot + 1, None);
                 }
-                Entity::Captured(n) => return self.captured[*n as usize].clone(),
-                Entity::Lambda(all, to_capture) => {
-                    let entries = &all[1..];
-                    let mut buf = Vec::with_capacity(to_capture.len());
-                    for c in to_capture.iter() {
-                        match c {
-                            Capturable::ParentParam(n) => {
-                                buf.push(self.params.clone_param(*n).clone())
-                            }
-                            _ => unimplemented!(),
+                memoized[slot] = Some(value);
+            }
+            Task::And(right, env) => match self.values.pop() {
+                Some(Value::Bool(true)) => self.tasks.push(Task::Eval(right, env)),
+                _ => self.values.push(Value::Bool(false)),
+            },
+            Task::Or(right, env) => match self.values.pop() {
+                Some(Value::Bool(true)) => self.values.push(Value::Bool(true)),
+                _ => self.tasks.push(Task::Eval(right, env)),
+            },
+            Task::Discard => {
+                self.values.pop();
+            }
+            Task::List(len) => {
+                let list = self.pop_values(len);
+                self.values
+                    .push(Value::List(Box::new(VecDeque::from(list))));
+            }
+            Task::Record(len) => {
+                let fields = self.pop_values(len);
+                self.values.push(Value::Struct(Box::new(fields)));
+            }
+            Task::Tuple(len) => {
+                let entries = self.pop_values(len);
+                self.values.push(Value::Tuple(Box::new(entries)));
+            }
+            Task::UpdateRecord(fields) => {
+                let updated = self.pop_values(fields.len());
+                match self.values.last_mut() {
+                    Some(Value::Struct(buf)) => {
+                        for ((i, _), v) in fields.iter().zip(updated) {
+                            buf[*i as usize] = v;
                         }
                     }
+                    _ => unreachable!(),
+                }
+            }
+            Task::FieldAccess(field) => match self.values.pop() {
+                Some(Value::Struct(mut fields)) => {
+                    self.values.push(fields.swap_remove(field as usize))
+                }
+                _ => unreachable!(),
+            },
+            Task::ConstructEnum(tag, name, len) => {
+                let params = self.pop_values(len);
+                self.values
+                    .push(Value::Enum(Box::new((tag, name.clone(), params))));
+            }
+            Task::Try(success) => match self.values.pop().unwrap() {
+                Value::Enum(box (tag, _, mut payload)) if tag == success => {
+                    self.values.push(payload.remove(0))
+                }
+                failed => self.early_return(failed),
+            },
+            Task::Exit => {
+                // Nothing else gets to run, so the code is left as the value `main` returns
+                let code = self.values.pop().unwrap();
+                self.tasks.clear();
+                self.values.clear();
+                self.values.push(code);
+            }
+            Task::Map(mapping) => {
+                let v = self.values.pop().unwrap();
+                self.map_next(mapping, Some(v));
+            }
+        }
+        Ok(())
+    }
 
-                    self.params = self.eval_params(entries);
-                    self.captured = buf;
-                    self.entity = &all[0];
+    fn eval(&mut self, entity: &'a Entity, env: Rc<Env>) -> Result<(), RuntimeError> {
+        #[cfg(debug_assertions)]
+        debug_dump_entity(entity, &env);
+
+        match entity {
+            Entity::RustCall(index, params) => {
+                self.tasks.push(Task::RustCall(*index, params.len()));
+                self.eval_all(params, &env);
+            }
+            Entity::Parameter(n) => self.values.push(env.params.clone_param(*n as usize)),
+            Entity::Inlined(v) => self.values.push(v.clone()),
+            Entity::IfExpression(expr) => self.branch(expr, 0, env),
+            Entity::FirstStatement(stmt) => {
+                self.tasks.push(Task::Eval(stmt.to_eval(), env.clone()));
+                for entity in stmt.to_void().rev() {
+                    self.tasks.push(Task::Discard);
+                    self.tasks.push(Task::Eval(entity, env.clone()));
+                }
+            }
+            Entity::List(list) => {
+                self.tasks.push(Task::List(list.len()));
+                self.eval_all(list, &env);
+            }
+            Entity::ParameterCall(paramid, params) => {
+                match env.params.clone_param(*paramid as usize) {
+                    Value::Function(box (findex, captured)) => {
+                        self.tasks
+                            .push(Task::CallClosure(findex, Rc::new(captured), params.len()))
+                    }
+                    _ => unreachable!(),
                 }
-                Entity::FunctionCall(findex, params) => {
-                    self.params = self.eval_params(params);
-                    let entity = &self.runtime.instructions[*findex as usize];
-                    self.entity = entity;
+                self.eval_all(params, &env);
+            }
+            Entity::CapturedCall(capid, params) => {
+                match env.captured[*capid as usize].clone() {
+                    Value::Function(box (findex, captured)) => {
+                        self.tasks
+                            .push(Task::CallClosure(findex, Rc::new(captured), params.len()))
+                    }
+                    _ => unreachable!(),
                 }
-                Entity::LambdaPointer(box (inner, to_capture)) => {
-                    let mut captured = Vec::with_capacity(to_capture.len());
-                    for capturable in to_capture.iter() {
-                        match capturable {
-                            Capturable::ParentParam(id) => {
-                                captured.push(self.params.clone_param(*id).clone())
-                            }
-                            Capturable::ParentLambda(_id) => {
-                                unreachable!()
-                            }
-                            Capturable::ParentWhere(_) => unimplemented!("`where <identifier>:` values cannot be captured into closures (yet)"),
-                        }
+                self.eval_all(params, &env);
+            }
+            Entity::Captured(n) => self.values.push(env.captured[*n as usize].clone()),
+            Entity::Match(expr) => {
+                self.tasks.push(Task::Match(expr, env.clone()));
+                self.tasks.push(Task::Eval(expr.value(), env));
+            }
+            Entity::Let(box (value, body)) => {
+                self.tasks.push(Task::Let(body, env.clone()));
+                self.tasks.push(Task::Eval(value, env));
+            }
+            Entity::Memoized(slot, inner) => {
+                let known = env.memoized.borrow().get(*slot as usize).cloned().flatten();
+                match known {
+                    Some(value) => self.values.push(value),
+                    None => {
+                        self.tasks.push(Task::Memoize(*slot, env.clone()));
+                        self.tasks.push(Task::Eval(inner, env));
                     }
-                    return Value::Function(Box::new((inner.clone(), captured)));
                 }
-                Entity::ConstructRecord(fields) => return self.record(fields),
-                Entity::Unimplemented => panic!("TODO: Unimplemented escapes"),
-                Entity::Unique => unreachable!(),
             }
+            Entity::And(box (left, right)) => {
+                self.tasks.push(Task::And(right, env.clone()));
+                self.tasks.push(Task::Eval(left, env));
+            }
+            Entity::Or(box (left, right)) => {
+                self.tasks.push(Task::Or(right, env.clone()));
+                self.tasks.push(Task::Eval(left, env));
+            }
+            Entity::Lambda(all, to_capture) => {
+                let entries = &all[1..];
+                self.tasks
+                    .push(Task::CallLambda(&all[0], to_capture.len(), entries.len()));
+                self.eval_all(entries, &env);
+                self.capture(to_capture, &env);
+            }
+            Entity::FunctionCall(findex, params) => {
+                self.tasks.push(Task::Call(*findex, params.len()));
+                self.eval_all(params, &env);
+            }
+            Entity::LambdaPointer(findex, to_capture) => {
+                self.tasks
+                    .push(Task::MakeClosure(*findex, to_capture.len()));
+                self.capture(to_capture, &env);
+            }
+            Entity::ConstructRecord(fields) => {
+                self.tasks.push(Task::Record(fields.len()));
+                self.eval_all(fields, &env);
+            }
+            Entity::ConstructTuple(entries) => {
+                self.tasks.push(Task::Tuple(entries.len()));
+                self.eval_all(entries, &env);
+            }
+            Entity::UpdateRecord(record, fields) => {
+                self.tasks.push(Task::UpdateRecord(fields));
+                for (_, entity) in fields.iter().rev() {
+                    self.tasks.push(Task::Eval(entity, env.clone()));
+                }
+                self.tasks.push(Task::Eval(record, env));
+            }
+            Entity::FieldAccess(record, field) => {
+                self.tasks.push(Task::FieldAccess(*field));
+                self.tasks.push(Task::Eval(record, env));
+            }
+            Entity::ConstructEnum(tag, name, params) => {
+                self.tasks
+                    .push(Task::ConstructEnum(*tag, name, params.len()));
+                self.eval_all(params, &env);
+            }
+            Entity::Try(inner, success) => {
+                self.tasks.push(Task::Try(*success));
+                self.tasks.push(Task::Eval(inner, env));
+            }
+            Entity::Exit(code) => {
+                self.tasks.push(Task::Exit);
+                self.tasks.push(Task::Eval(code, env));
+            }
+            Entity::Unimplemented => return Err(RuntimeFault::Unimplemented.into()),
+            Entity::Unique => unreachable!(),
         }
+        Ok(())
     }
 
-    fn eval_params(&mut self, params: &'a [Entity]) -> ParamBuffer<'a> {
-        ParamBuffer::from(
-            params
-                .iter()
-                .map(|p| self.spawn(p, self.params.clone(), self.captured.clone())),
-        )
+    // Tasks run in reverse order of being pushed, so this leaves the values in order on the stack
+    fn eval_all(&mut self, entities: &'a [Entity], env: &Rc<Env>) {
+        for entity in entities.iter().rev() {
+            self.tasks.push(Task::Eval(entity, env.clone()));
+        }
     }
 
-    fn record(self, fields: &'a [Entity]) -> Value {
-        let mut buf = Vec::with_capacity(fields.len());
-        for entity in fields {
-            let v = self.spawn(entity, self.params.clone(), self.captured.clone());
-            buf.push(v);
+    fn capture(&mut self, to_capture: &'a [Capturable], env: &Rc<Env>) {
+        for capturable in to_capture.iter().rev() {
+            let task = match capturable {
+                Capturable::ParentParam(n) => Task::Push(env.params.clone_param(*n)),
+                Capturable::ParentLambda(n) => Task::Push(env.captured[*n].clone()),
+                Capturable::ParentWhere(entity) => Task::Eval(entity, env.clone()),
+            };
+            self.tasks.push(task);
         }
-        Value::Struct(Box::new(buf))
     }
 
-    fn rust_call(mut self, index: Bridged, rust_params: &'a [Entity]) -> Value {
-        self.params = self.eval_params(rust_params);
-        self.eval_bridged(index)
+    // A call in tail position already has the `Return` of the function it's replacing on top, so
+    // tail calls don't grow the task stack.
+    fn enter(&mut self, findex: Option<u32>, body: &'a Entity, env: Env) {
+        match self.tasks.last_mut() {
+            Some(Task::Return(current, tail, _)) => {
+                if let Some(replaced) = findex.and_then(|findex| current.replace(findex)) {
+                    let elided = tail.as_ref().map(|tail| tail.elided + 1).unwrap_or(0);
+                    *tail = Some(TailCall {
+                        caller: replaced,
+                        elided,
+                    });
+                }
+            }
+            _ => self
+                .tasks
+                .push(Task::Return(findex, None, self.values.len())),
+        }
+        self.tasks.push(Task::Eval(body, Rc::new(env)));
     }
-    fn if_expression(mut self, expr: &'a If<Entity>) -> Value {
-        for i in 0..expr.branches() {
-            let cond = expr.condition(i);
-            if let Value::Bool(true) = self.spawn(cond, self.params.clone(), self.captured.clone())
-            {
-                self.entity = expr.evaluation(i);
-                return self.run();
+
+    fn early_return(&mut self, value: Value) {
+        while let Some(task) = self.tasks.pop() {
+            if let Task::Return(_, _, height) = task {
+                self.values.truncate(height);
+                break;
             }
         }
-        self.entity = expr.r#else();
-        self.run()
+        self.values.push(value);
+    }
+
+    fn branch(&mut self, expr: &'a If<Entity>, i: usize, env: Rc<Env>) {
+        if i < expr.branches() {
+            self.tasks.push(Task::If(expr, i, env.clone()));
+            self.tasks.push(Task::Eval(expr.condition(i), env));
+        } else {
+            self.tasks.push(Task::Eval(expr.r#else(), env));
+        }
+    }
+
+    fn pop_values(&mut self, n: usize) -> Vec<Value> {
+        let at = self.values.len() - n;
+        self.values.split_off(at)
+    }
+
+    fn pop_params(&mut self, n: usize) -> ParamBuffer {
+        let values = self.pop_values(n);
+        ParamBuffer::from(values.into_iter())
     }
-    fn first_statement(mut self, stmt: &'a First<Entity>) -> Value {
-        for entity in stmt.to_void() {
-            self.spawn(entity, self.params.clone(), self.captured.clone());
+}
+
+impl Env {
+    fn extended(&self, values: impl Iterator<Item = Value>) -> Env {
+        Env {
+            params: self.params.extended(values),
+            captured: self.captured.clone(),
+            memoized: self.memoized.clone(),
         }
-        self.entity = stmt.to_eval();
-        self.run()
     }
-    fn list(mut self, list: &'a [Entity]) -> Value {
-        let mut buf = VecDeque::with_capacity(list.len());
-        for entity in list[0..list.len() - 1].iter() {
-            buf.push_back(self.spawn(entity, self.params.clone(), self.captured.clone()))
+}
+
+// Values bound by the pattern are pushed in the same order as the checker assigned them parameter
+// indexes
+fn matches(pattern: &Pattern, value: &Value, bound: &mut Vec<Value>) -> bool {
+    match (pattern, value) {
+        (Pattern::Wildcard, _) => true,
+        (Pattern::Bind, _) => {
+            bound.push(value.clone());
+            true
+        }
+        (Pattern::Literal(v), _) => v == value,
+        (Pattern::Variant(tag, params), Value::Enum(box (vtag, _, payload))) => {
+            tag == vtag
+                && params
+                    .iter()
+                    .zip(payload.iter())
+                    .all(|(p, v)| matches(p, v, bound))
+        }
+        (Pattern::Tuple(entries), Value::Tuple(fields)) => entries
+            .iter()
+            .zip(fields.iter())
+            .all(|(p, v)| matches(p, v, bound)),
+        (Pattern::List(entries, rest), Value::List(list)) => {
+            let fits = match rest {
+                None => list.len() == entries.len(),
+                Some(_) => list.len() >= entries.len(),
+            };
+            if !fits
+                || !entries
+                    .iter()
+                    .zip(list.iter())
+                    .all(|(p, v)| matches(p, v, bound))
+            {
+                return false;
+            }
+            match rest {
+                None => true,
+                Some(rest) => {
+                    let tail = list.iter().skip(entries.len()).cloned().collect();
+                    matches(rest, &Value::List(Box::new(tail)), bound)
+                }
+            }
         }
-        buf.push_back({
-            self.entity = &list[list.len() - 1];
-            self.run()
-        });
-        Value::List(Box::new(buf))
+        _ => false,
     }
 }
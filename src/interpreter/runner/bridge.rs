@@ -1,113 +1,212 @@
-use super::ParamBuffer;
-use super::Runner;
+use super::{Env, ParamBuffer, Runner, Task};
+use crate::interpreter::{RuntimeError, RuntimeFault};
 use crate::ir::bridge::Bridged;
 use crate::ir::Value;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::rc::Rc;
 
+// Ints are checked since they come from leaf code, floats follow IEEE 754 instead
 macro_rules! calc {
-    ($op:tt, $x:expr, $y:expr) => (
-        match $x {
-            Value::Int(x) => match $y {
-                Value::Int(y) => return Value::Int(*x $op *y),
-                _ => unreachable!(),
+    ($checked:ident, $op:tt, $x:expr, $y:expr) => (
+        match ($x, $y) {
+            (Value::Int(x), Value::Int(y)) => match x.$checked(*y) {
+                Some(n) => return Ok(Value::Int(n)),
+                None => return Err(RuntimeFault::IntegerOverflow),
             }
-            Value::Float(x) => match $y {
-                Value::Float(y) => return Value::Float(*x $op *y),
-                _ => unreachable!(),
-            }
-            _ => unreachable!(),
+            (Value::Float(x), Value::Float(y)) => return Ok(Value::Float(*x $op *y)),
+            _ => return Err(RuntimeFault::UnexpectedValues),
         }
     )
 }
 
-impl<'p> Runner<'p> {
-    pub fn eval_bridged(self, func: Bridged) -> Value {
-        // let (x, y) = (self.params.clone_param(0), self.params.clone_param(1));
-        match func {
-            Bridged::add => calc!(+, self.params.borrow_param(0), self.params.borrow_param(1)),
-            Bridged::sub => calc!(-, self.params.borrow_param(0), self.params.borrow_param(1)),
-            Bridged::mul => calc!(*, self.params.borrow_param(0), self.params.borrow_param(1)),
-            Bridged::div => calc!(/, self.params.borrow_param(0), self.params.borrow_param(1)),
-            Bridged::push_back => {
-                if let Value::List(mut list) = self.params.clone_param(1) {
-                    list.push_back(self.params.clone_param(0));
-                    return Value::List(list);
+// `map_overwrite` calls back into leaf, so it's driven by the runner one element at a time
+pub struct Mapping {
+    findex: u32,
+    captured: Rc<Vec<Value>>,
+    remaining: VecDeque<Value>,
+    done: VecDeque<Value>,
+}
+
+impl<'a> Runner<'a> {
+    pub fn eval_bridged(&mut self, func: Bridged, params: ParamBuffer) -> Result<(), RuntimeError> {
+        if let Bridged::map_overwrite = func {
+            // TODO: This implementation is temporary. The cloning here is obviously bad.
+            if let Value::List(list) = params.clone_param(1) {
+                if let Value::Function(box (findex, captured)) = params.clone_param(0) {
+                    let mapping = Mapping {
+                        findex,
+                        captured: Rc::new(captured),
+                        done: VecDeque::with_capacity(list.len()),
+                        remaining: *list,
+                    };
+                    self.map_next(Box::new(mapping), None);
+                    return Ok(());
                 }
-                unreachable!();
             }
-            Bridged::push_front => {
-                if let Value::List(mut list) = self.params.clone_param(1) {
-                    list.push_front(self.params.clone_param(0));
-                    return Value::List(list);
-                }
-                unreachable!();
+            return Err(RuntimeFault::UnexpectedValues.in_builtin(func, params.as_slice()));
+        }
+        let v =
+            bridged(func, &params).map_err(|fault| fault.in_builtin(func, params.as_slice()))?;
+        self.values.push(v);
+        Ok(())
+    }
+
+    pub fn map_next(&mut self, mut mapping: Box<Mapping>, previous: Option<Value>) {
+        if let Some(v) = previous {
+            mapping.done.push_back(v);
+        }
+        match mapping.remaining.pop_front() {
+            Some(v) => {
+                let runtime = self.runtime;
+                let findex = mapping.findex;
+                let body = &runtime.instructions[findex as usize];
+                let captured = mapping.captured.clone();
+                self.tasks.push(Task::Map(mapping));
+                self.enter(
+                    Some(findex),
+                    body,
+                    Env {
+                        params: ParamBuffer::from(std::iter::once(v)),
+                        captured,
+                        memoized: Rc::default(),
+                    },
+                );
             }
-            Bridged::get => {
-                if let Value::List(list) = self.params.borrow_param(1) {
-                    if let Value::Int(i) = self.params.clone_param(0) {
-                        return list[i as usize].clone();
-                    }
-                }
-                unreachable!();
+            None => self.values.push(Value::List(Box::new(mapping.done))),
+        }
+    }
+}
+
+fn bridged(func: Bridged, params: &ParamBuffer) -> Result<Value, RuntimeFault> {
+    Ok(match func {
+        Bridged::div | Bridged::rem if matches!(params.borrow_param(1), Value::Int(0)) => {
+            return Err(RuntimeFault::DivisionByZero)
+        }
+        Bridged::add => calc!(checked_add, +, params.borrow_param(0), params.borrow_param(1)),
+        Bridged::sub => calc!(checked_sub, -, params.borrow_param(0), params.borrow_param(1)),
+        Bridged::mul => calc!(checked_mul, *, params.borrow_param(0), params.borrow_param(1)),
+        Bridged::div => calc!(checked_div, /, params.borrow_param(0), params.borrow_param(1)),
+        Bridged::rem => calc!(checked_rem, %, params.borrow_param(0), params.borrow_param(1)),
+        Bridged::to_float => match params.borrow_param(0) {
+            Value::Int(n) => Value::Float(*n as f64),
+            _ => return Err(RuntimeFault::UnexpectedValues),
+        },
+        // Conversions to int round towards zero
+        Bridged::to_int => match params.borrow_param(0) {
+            Value::Float(n) => Value::Int(float_to_int(n.trunc())?),
+            _ => return Err(RuntimeFault::UnexpectedValues),
+        },
+        Bridged::round => match params.borrow_param(0) {
+            Value::Float(n) => Value::Int(float_to_int(n.round())?),
+            _ => return Err(RuntimeFault::UnexpectedValues),
+        },
+        Bridged::floor => match params.borrow_param(0) {
+            Value::Float(n) => Value::Int(float_to_int(n.floor())?),
+            _ => return Err(RuntimeFault::UnexpectedValues),
+        },
+        Bridged::push_back => {
+            if let Value::List(mut list) = params.clone_param(1) {
+                list.push_back(params.clone_param(0));
+                return Ok(Value::List(list));
+            }
+            return Err(RuntimeFault::UnexpectedValues);
+        }
+        Bridged::push_front => {
+            if let Value::List(mut list) = params.clone_param(1) {
+                list.push_front(params.clone_param(0));
+                return Ok(Value::List(list));
             }
-            Bridged::len => {
-                if let Value::List(list) = self.params.borrow_param(0) {
-                    Value::Int(list.len() as i64)
-                } else {
-                    dbg!(&self.params);
-                    unreachable!("{:?}", self.params.borrow_param(0))
+            return Err(RuntimeFault::UnexpectedValues);
+        }
+        Bridged::get => {
+            if let Value::List(list) = params.borrow_param(1) {
+                if let Value::Int(i) = params.clone_param(0) {
+                    return Ok(list[index(i, list.len())?].clone());
                 }
             }
-            // TODO: This should be reimplemented in Leaf when we have a stronger type system
-            // or should it?
-            Bridged::eq => Value::Bool(self.params.borrow_param(0) == self.params.borrow_param(1)),
-            Bridged::lt => Value::Bool(self.params.borrow_param(0) < self.params.borrow_param(1)),
-            Bridged::steal => {
-                if let Value::List(mut list) = self.params.clone_param(1) {
-                    if let Value::Int(i) = self.params.borrow_param(0) {
-                        return list.remove(*i as usize).unwrap();
-                    }
+            return Err(RuntimeFault::UnexpectedValues);
+        }
+        Bridged::len => match params.borrow_param(0) {
+            Value::List(list) => Value::Int(list.len() as i64),
+            Value::String(s) => Value::Int(s.chars().count() as i64),
+            _ => return Err(RuntimeFault::UnexpectedValues),
+        },
+        Bridged::eq => Value::Bool(compare_params(params)? == Some(Ordering::Equal)),
+        Bridged::lt => Value::Bool(compare_params(params)? == Some(Ordering::Less)),
+        Bridged::le => Value::Bool(matches!(
+            compare_params(params)?,
+            Some(Ordering::Less) | Some(Ordering::Equal)
+        )),
+        Bridged::steal => {
+            if let Value::List(mut list) = params.clone_param(1) {
+                if let Value::Int(i) = params.borrow_param(0) {
+                    let i = index(*i, list.len())?;
+                    return Ok(list.remove(i).unwrap());
                 }
-                unreachable!();
             }
-            Bridged::remove => {
-                if let Value::List(mut list) = self.params.clone_param(1) {
-                    if let Value::Int(i) = self.params.borrow_param(0) {
-                        list.remove(*i as usize);
-                        return Value::List(list);
-                    }
+            return Err(RuntimeFault::UnexpectedValues);
+        }
+        Bridged::remove => {
+            if let Value::List(mut list) = params.clone_param(1) {
+                if let Value::Int(i) = params.borrow_param(0) {
+                    list.remove(index(*i, list.len())?);
+                    return Ok(Value::List(list));
                 }
-                unreachable!();
             }
-            Bridged::print_any => {
-                println!("{}", self.params.borrow_param(0));
-                Value::Nothing
+            return Err(RuntimeFault::UnexpectedValues);
+        }
+        Bridged::print_any => {
+            match params.borrow_param(0) {
+                // Strings are printed raw, while strings nested in other values stay quoted
+                Value::String(s) => println!("{}", s),
+                other => println!("{}", other),
             }
-            Bridged::map_overwrite => {
-                // TODO: This implementation is temporary. The cloning here is obviously bad.
-                if let Value::List(mut list) = self.params.clone_param(1) {
-                    if let Value::Function(box (action, captured)) = self.params.clone_param(0) {
-                        for previous in list.iter_mut() {
-                            let new = self.spawn(
-                                &action,
-                                ParamBuffer::Owned(smallvec![previous.clone()]),
-                                captured.clone(),
-                            );
-                            *previous = new;
-                        }
-                        return Value::List(list);
-                    }
-                }
-                unreachable!();
+            Value::Nothing
+        }
+        // Driven by the runner in `eval_bridged` instead
+        Bridged::map_overwrite => return Err(RuntimeFault::UnexpectedValues),
+        Bridged::append => match (params.borrow_param(0), params.borrow_param(1)) {
+            (Value::List(list), Value::List(addition)) => {
+                let mut list = list.clone();
+                list.extend(addition.iter().cloned());
+                Value::List(list)
             }
-            Bridged::append => {
-                if let Value::List(mut list) = self.params.clone_param(0) {
-                    if let Value::List(mut addition) = self.params.clone_param(1) {
-                        list.append(&mut addition);
-                        return Value::List(list);
-                    }
-                }
-                unreachable!();
+            (Value::String(s), Value::String(addition)) => {
+                Value::String([&**s, &**addition].concat().into())
             }
+            _ => return Err(RuntimeFault::UnexpectedValues),
+        },
+    })
+}
+
+// Unordered floats aren't equal to anything, but values that can't be compared at all are a fault
+fn compare_params(params: &ParamBuffer) -> Result<Option<Ordering>, RuntimeFault> {
+    let (a, b) = (params.borrow_param(0), params.borrow_param(1));
+    match (a, b) {
+        (Value::Function(_), _) | (_, Value::Function(_)) => Err(RuntimeFault::UnexpectedValues),
+        _ if std::mem::discriminant(a) != std::mem::discriminant(b) => {
+            Err(RuntimeFault::UnexpectedValues)
         }
+        _ => Ok(a.compare(b)),
+    }
+}
+
+// Floats outside the range of an int, infinities and NaN have no int to convert to.
+// `i64::MAX as f64` rounds up to 2^63, which is already out of range.
+fn float_to_int(n: f64) -> Result<i64, RuntimeFault> {
+    if n.is_finite() && n >= i64::MIN as f64 && n < i64::MAX as f64 {
+        Ok(n as i64)
+    } else {
+        Err(RuntimeFault::IntegerOverflow)
+    }
+}
+
+// Indexes come from leaf code, so they're checked rather than trusted
+fn index(i: i64, len: usize) -> Result<usize, RuntimeFault> {
+    if i < 0 || i as usize >= len {
+        Err(RuntimeFault::IndexOutOfBounds(i, len))
+    } else {
+        Ok(i as usize)
     }
 }
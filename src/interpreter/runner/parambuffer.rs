@@ -3,77 +3,48 @@ use smallvec::SmallVec;
 use std::fmt;
 use std::ops::{Index, IndexMut};
 
-pub enum ParamBuffer<'p> {
-    Borrowed(&'p SmallVec<[Value; 4]>),
-    Owned(SmallVec<[Value; 4]>),
-}
-
-impl Default for ParamBuffer<'_> {
-    fn default() -> Self {
-        Self::Owned(SmallVec::new())
-    }
-}
+#[derive(Default, Clone)]
+pub struct ParamBuffer(SmallVec<[Value; 4]>);
 
-impl<'p> ParamBuffer<'p> {
+impl ParamBuffer {
     pub fn clone_param(&self, n: usize) -> Value {
-        match self {
-            ParamBuffer::Owned(vec) => vec[n].clone(),
-            ParamBuffer::Borrowed(vec) => vec[n].clone(),
-        }
+        self.0[n].clone()
     }
     pub fn borrow_param(&self, n: usize) -> &Value {
-        match self {
-            ParamBuffer::Owned(vec) => &vec[n],
-            ParamBuffer::Borrowed(vec) => &vec[n],
-        }
+        &self.0[n]
+    }
+    pub fn extended(&self, values: impl Iterator<Item = Value>) -> ParamBuffer {
+        ParamBuffer::from(self.as_slice().iter().cloned().chain(values))
     }
     pub fn as_slice(&self) -> &[Value] {
-        match self {
-            Self::Borrowed(v) => v.as_slice(),
-            Self::Owned(v) => v.as_slice(),
-        }
+        self.0.as_slice()
     }
 }
-impl<'p> Index<usize> for ParamBuffer<'p> {
+impl Index<usize> for ParamBuffer {
     type Output = Value;
 
     fn index(&self, index: usize) -> &Self::Output {
-        match self {
-            Self::Borrowed(vec) => &vec[index],
-            Self::Owned(vec) => &vec[index],
-        }
+        &self.0[index]
     }
 }
 
-impl<'p> IndexMut<usize> for ParamBuffer<'p> {
+impl IndexMut<usize> for ParamBuffer {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        match self {
-            Self::Borrowed(_) => unreachable!(),
-            Self::Owned(vec) => &mut vec[index],
-        }
+        &mut self.0[index]
     }
 }
 
-impl<I: Iterator<Item = Value>> From<I> for ParamBuffer<'_> {
+impl<I: Iterator<Item = Value>> From<I> for ParamBuffer {
     fn from(iter: I) -> Self {
         let mut vec = SmallVec::new();
         for v in iter {
             vec.push(v);
         }
-        Self::Owned(vec)
-    }
-}
-
-impl Clone for ParamBuffer<'_> {
-    fn clone(&self) -> Self {
-        match self {
-            ParamBuffer::Owned(vec) => ParamBuffer::Owned(vec.clone()),
-            ParamBuffer::Borrowed(vec) => ParamBuffer::Owned((*vec).to_owned()),
-        }
+        Self(vec)
     }
 }
 
-impl fmt::Debug for ParamBuffer<'_> {
+impl fmt::Debug for ParamBuffer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self.as_slice())
     }
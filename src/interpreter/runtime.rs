@@ -3,11 +3,16 @@ use crate::ir::Entity;
 
 pub struct Runtime {
     pub instructions: Vec<ir::Entity>,
+    // Indexed the same as `instructions`
+    pub origins: Vec<ir::Origin>,
 }
 
 impl Runtime {
-    pub fn new(instructions: Vec<ir::Entity>) -> Self {
-        Self { instructions }
+    pub fn new(instructions: Vec<ir::Entity>, origins: Vec<ir::Origin>) -> Self {
+        Self {
+            instructions,
+            origins,
+        }
     }
 }
 
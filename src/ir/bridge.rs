@@ -32,12 +32,18 @@ pub enum Bridged {
     sub,
     mul,
     div,
+    rem,
+    to_float,
+    to_int,
+    round,
+    floor,
     push_back,
     push_front,
     get,
     len,
     eq,
     lt,
+    le,
     steal,
     remove,
     print_any,
@@ -52,12 +58,18 @@ pub fn get_funcid(ident: &str) -> Result<(Bridged, NaiveType), ParseFault> {
         "sub" => (sub, NaiveType::Matching(0)),
         "mul" => (mul, NaiveType::Matching(0)),
         "div" => (div, NaiveType::Matching(0)),
+        "rem" => (rem, NaiveType::Matching(0)),
+        "to_float" => (to_float, NaiveType::Known(Type::Float)),
+        "to_int" => (to_int, NaiveType::Known(Type::Int)),
+        "round" => (round, NaiveType::Known(Type::Int)),
+        "floor" => (floor, NaiveType::Known(Type::Int)),
         "push_back" => (push_back, NaiveType::Matching(1)),
         "push_front" => (push_front, NaiveType::Matching(1)),
         "get" => (get, NaiveType::UnlistedMatching(1)),
         "len" => (len, NaiveType::Known(Type::Int)),
         "eq" => (eq, NaiveType::Known(Type::Bool)),
         "lt" => (lt, NaiveType::Known(Type::Bool)),
+        "le" => (le, NaiveType::Known(Type::Bool)),
         "steal" => (steal, NaiveType::Matching(1)),
         "remove" => (remove, NaiveType::Matching(1)),
         "print_any" => (print_any, NaiveType::Known(Type::Nothing)),
@@ -0,0 +1,84 @@
+use super::Value;
+use std::fmt;
+
+// Patterns which passed the type checker. Bindings don't have names here. Instead each `Bind`
+// appends its value to the parameters of the arm in the order they're encountered.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Wildcard,
+    Bind,
+    Literal(Value),
+    Variant(u16, Vec<Pattern>),
+    List(Vec<Pattern>, Option<Box<Pattern>>),
+    Tuple(Vec<Pattern>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Match<T> {
+    value: T,
+    arms: Vec<(Pattern, T)>,
+}
+
+impl<T> Match<T> {
+    pub fn new(value: T, arms: Vec<(Pattern, T)>) -> Self {
+        Self { value, arms }
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+    pub fn arms(&self) -> &[(Pattern, T)] {
+        &self.arms
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Bind => write!(f, "$"),
+            Pattern::Literal(v) => write!(f, "{}", v),
+            Pattern::Variant(tag, params) => {
+                write!(f, "#{}", tag)?;
+                for p in params.iter() {
+                    write!(f, " {}", p)?;
+                }
+                Ok(())
+            }
+            Pattern::List(entries, rest) => {
+                write!(
+                    f,
+                    "[{}",
+                    entries
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )?;
+                if let Some(rest) = rest {
+                    write!(f, "|{}", rest)?;
+                }
+                write!(f, "]")
+            }
+            Pattern::Tuple(entries) => write!(
+                f,
+                "({})",
+                entries
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Match<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(match {}", self.value)?;
+        for (pattern, eval) in self.arms.iter() {
+            write!(f, " |{}: {}", pattern, eval)?;
+        }
+        write!(f, ")")
+    }
+}
@@ -4,10 +4,15 @@ mod r#if;
 pub use r#if::If;
 mod first;
 pub use first::First;
+mod r#match;
+pub use r#match::{Match, Pattern};
 mod value;
 pub use value::Value;
+mod origin;
+pub use origin::Origin;
 
 use std::fmt;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub enum Entity {
@@ -17,14 +22,32 @@ pub enum Entity {
     CapturedCall(u32, Vec<Entity>),
     IfExpression(self::If<Entity>),
     FirstStatement(self::First<Entity>),
+    Match(Box<self::Match<Entity>>),
     Parameter(u16),
     Captured(u16),
     ConstructRecord(Vec<Entity>),
+    ConstructTuple(Vec<Entity>),
+    ConstructEnum(u16, Rc<str>, Vec<Entity>),
+    UpdateRecord(Box<Entity>, Vec<(u16, Entity)>),
+    FieldAccess(Box<Entity>, u16),
+    // The variant with the given tag (`Just`/`Ok`) is unwrapped, any other variant is returned
+    // from the function as-is
+    Try(Box<Entity>, u16),
+    // Evaluates the value once and appends it to the parameters for the body
+    Let(Box<(Entity, Entity)>),
+    // Evaluated the first time it's reached, later uses in the same call reuse the value
+    Memoized(u16, Box<Entity>),
+    // The right side is only evaluated if the left side didn't already decide the result
+    And(Box<(Entity, Entity)>),
+    Or(Box<(Entity, Entity)>),
+    // Ends the program, the status code becomes what `main` returns
+    Exit(Box<Entity>),
 
     Inlined(Value),
     List(Vec<Entity>),
     Lambda(Vec<Entity>, Vec<Capturable>),
-    LambdaPointer(Box<(Entity, Vec<Capturable>)>),
+    // The body of a closure is completed as its own instruction, this is its index
+    LambdaPointer(u32, Vec<Capturable>),
 
     Unimplemented,
     Unique,
@@ -49,21 +72,10 @@ impl Entity {
     }
 }
 
-impl PartialEq for Entity {
-    fn eq(&self, _: &Self) -> bool {
-        panic!("ET: Invalid comparison between types. This is a temporary error for when we have a more powerfull leaf type system")
-    }
-}
-impl PartialOrd for Entity {
-    fn partial_cmp(&self, _: &Self) -> Option<std::cmp::Ordering> {
-        panic!("ET: Invalid comparison between types. This is a temporary error for when we have a more powerfull leaf type system")
-    }
-}
-
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Capturable {
     ParentParam(usize),
-    ParentWhere(usize),
+    ParentWhere(Entity),
     ParentLambda(usize),
 }
 
@@ -72,7 +84,7 @@ impl fmt::Display for Capturable {
         match self {
             Capturable::ParentParam(i) => write!(f, "p{{{}}}", i),
             Capturable::ParentLambda(i) => write!(f, "l{{{}}}", i),
-            Capturable::ParentWhere(i) => write!(f, "w{{{}}}", i),
+            Capturable::ParentWhere(v) => write!(f, "w{{{}}}", v),
         }
     }
 }
@@ -98,6 +110,39 @@ impl fmt::Display for Entity {
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
+            Entity::ConstructTuple(entries) => write!(
+                f,
+                "({})",
+                entries
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Entity::ConstructEnum(_tag, name, params) => {
+                write!(f, "({}", name)?;
+                for p in params.iter() {
+                    write!(f, " {}", p)?
+                }
+                write!(f, ")")
+            }
+            Entity::UpdateRecord(record, fields) => write!(
+                f,
+                "{{ {} }} << {}",
+                fields
+                    .iter()
+                    .map(|(i, v)| format!("{} {}", i, v))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                record
+            ),
+            Entity::FieldAccess(record, field) => write!(f, "{}.{}", record, field),
+            Entity::Try(inner, tag) => write!(f, "(try #{} {})", tag, inner),
+            Entity::Let(box (value, body)) => write!(f, "(let {} in {})", value, body),
+            Entity::Memoized(slot, inner) => write!(f, "(memo-{} {})", slot, inner),
+            Entity::And(box (left, right)) => write!(f, "({} and {})", left, right),
+            Entity::Or(box (left, right)) => write!(f, "({} or {})", left, right),
+            Entity::Exit(code) => write!(f, "(exit {})", code),
             Entity::FunctionCall(findex, params) => {
                 write!(f, "(call-{}", findex)?;
                 for p in params.iter() {
@@ -121,6 +166,7 @@ impl fmt::Display for Entity {
             }
             Entity::IfExpression(branches) => branches.fmt(f),
             Entity::FirstStatement(branches) => branches.fmt(f),
+            Entity::Match(m) => m.fmt(f),
             Entity::Parameter(i) => write!(f, "p{}", i),
             Entity::Captured(i) => write!(f, "cb{}", i),
             Entity::Inlined(v) => write!(f, "{}", v),
@@ -146,14 +192,13 @@ impl fmt::Display for Entity {
                 write!(f, "}}")?;
                 write!(f, " {})", params.first().unwrap())
             }
-            Entity::LambdaPointer(box (body, captures)) => {
-                write!(f, "(lambda")?;
+            Entity::LambdaPointer(findex, captures) => {
+                write!(f, "(lambda-{}", findex)?;
                 write!(f, " {{")?;
                 for cap in captures.iter() {
                     write!(f, " {}", cap)?;
                 }
-                write!(f, "}}")?;
-                write!(f, " {})", body)
+                write!(f, "}})")
             }
             Entity::Unimplemented => write!(f, "unimp"),
             Entity::Unique => write!(f, "unique"),
@@ -0,0 +1,36 @@
+use crate::parser::FileSource;
+use std::fmt;
+
+// Where an IR function came from, kept to the side of the instructions so that runtime errors can
+// point back into the source code
+#[derive(Debug, Clone)]
+pub struct Origin {
+    pub name: String,
+    pub operator: bool,
+    // Closures are completed as functions of their own, `name` is then the function they're in
+    pub lambda: bool,
+    pub module: FileSource,
+    pub source_index: usize,
+}
+
+impl Default for Origin {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            operator: false,
+            lambda: false,
+            module: FileSource::Prelude,
+            source_index: 0,
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.lambda {
+            write!(f, "lambda in ")?;
+        }
+        let header = if self.operator { "operator" } else { "fn" };
+        write!(f, "{} {}", header, self.name)
+    }
+}
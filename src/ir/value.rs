@@ -1,15 +1,22 @@
+use std::cmp::Ordering;
 use std::collections::VecDeque;
 use std::fmt;
+use std::rc::Rc;
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone)]
 pub enum Value {
     Nothing,
     Int(i64),
     Float(f64),
     Bool(bool),
+    // Strings are never modified in place, so clones can share them
+    String(Rc<str>),
     Struct(Box<Vec<Value>>),
-    // I want *actually* captured here, not to be captured.
-    Function(Box<(super::Entity, Vec<Value>)>),
+    Tuple(Box<Vec<Value>>),
+    // The variant name is carried along purely for display purposes, matching is done on the tag
+    Enum(Box<(u16, Rc<str>, Vec<Value>)>),
+    // The index of the closure body and its captured values
+    Function(Box<(u32, Vec<Value>)>),
 
     // TODO: This is a terrible way to handle lists.
     // For one, we're storing meta type information for each member of the list
@@ -24,6 +31,59 @@ pub enum Value {
     List(Box<VecDeque<Value>>),
 }
 
+impl Value {
+    // Structural comparison between two values of the same type. Records and tuples are compared
+    // entry by entry and enums by variant and then their parameters. NaN isn't ordered, so that
+    // gives `None`.
+    //
+    // Functions and values of different types can't be compared either, which also gives `None`.
+    // The checker rejects comparing them.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Nothing, Value::Nothing) => Some(Ordering::Equal),
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Struct(a), Value::Struct(b)) | (Value::Tuple(a), Value::Tuple(b)) => {
+                compare_all(a.iter(), b.iter())
+            }
+            (Value::Enum(box (a, _, a_params)), Value::Enum(box (b, _, b_params))) => {
+                match a.cmp(b) {
+                    Ordering::Equal => compare_all(a_params.iter(), b_params.iter()),
+                    ord => Some(ord),
+                }
+            }
+            (Value::List(a), Value::List(b)) => compare_all(a.iter(), b.iter()),
+            _ => None,
+        }
+    }
+}
+
+// Lexicographic, so a list is less than a longer list that it's the start of
+fn compare_all<'a>(
+    mut a: impl Iterator<Item = &'a Value>,
+    mut b: impl Iterator<Item = &'a Value>,
+) -> Option<Ordering> {
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Some(Ordering::Equal),
+            (None, Some(_)) => return Some(Ordering::Less),
+            (Some(_), None) => return Some(Ordering::Greater),
+            (Some(x), Some(y)) => match x.compare(y)? {
+                Ordering::Equal => continue,
+                ord => return Some(ord),
+            },
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        self.compare(other) == Some(Ordering::Equal)
+    }
+}
+
 impl Default for Value {
     fn default() -> Value {
         Value::Nothing
@@ -35,8 +95,10 @@ impl fmt::Display for Value {
         match self {
             Value::Nothing => write!(f, "_"),
             Value::Int(n) => write!(f, "{}", n),
-            Value::Float(n) => write!(f, "{}", n),
+            // Debug formatting keeps the `.0` of whole numbers
+            Value::Float(n) => write!(f, "{:?}", n),
             Value::Bool(b) => write!(f, "{}", b),
+            Value::String(s) => write!(f, "{:?}", s),
             Value::Struct(fields) => write!(
                 f,
                 "{}",
@@ -47,7 +109,28 @@ impl fmt::Display for Value {
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
-            Value::Function(box (body, _captured)) => write!(f, "f({})", body),
+            Value::Tuple(entries) => write!(
+                f,
+                "({})",
+                entries
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Enum(box (_tag, name, params)) => {
+                write!(f, "{}", name)?;
+                for p in params.iter() {
+                    match p {
+                        Value::Enum(box (_, _, inner)) if !inner.is_empty() => {
+                            write!(f, " ({})", p)?
+                        }
+                        _ => write!(f, " {}", p)?,
+                    }
+                }
+                Ok(())
+            }
+            Value::Function(box (findex, _captured)) => write!(f, "f({})", findex),
             Value::List(list) => {
                 write!(f, "[")?;
                 for (i, entity) in list.iter().enumerate() {
@@ -1,13 +1,14 @@
 #![feature(box_patterns)]
 #![feature(mem_take)]
 #![feature(print_internals)]
+use std::convert::TryFrom;
+use std::fmt;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::rc::Rc;
 
 mod macros;
 
-#[macro_use]
 extern crate smallvec;
 
 mod parser;
@@ -33,23 +34,65 @@ fn main() {
         return;
     }
     match run(environment.clone()) {
+        // An int returned from `main`, or given to `exit`, is used as the status code
+        Ok(ir::Value::Int(code)) => {
+            std::io::stdout().flush().unwrap();
+            std::process::exit(status_code(code));
+        }
         Ok(_main_returns) => {}
         Err(e) => {
             println!("{}", e);
             if environment.panicky {
                 panic!("leaf encountered an error");
             }
+            std::process::exit(1);
         }
     };
 }
 
-fn run(env: Rc<Environment>) -> Result<ir::Value, ParseError> {
-    let (ir, entrypoint) = {
+// Codes that don't fit in an `i32`, or that the OS would cut down to a successful `0` such as
+// `256`, still need to signal that something went wrong
+fn status_code(code: i64) -> i32 {
+    match i32::try_from(code) {
+        Ok(code) if code == 0 || code % 256 != 0 => code,
+        _ => 1,
+    }
+}
+
+// Anything that can stop a program, from parsing it to running it
+enum Failure {
+    Parse(Box<ParseError>),
+    Runtime(interpreter::RuntimeError),
+}
+
+impl From<ParseError> for Failure {
+    fn from(e: ParseError) -> Self {
+        Failure::Parse(Box::new(e))
+    }
+}
+
+impl From<interpreter::RuntimeError> for Failure {
+    fn from(e: interpreter::RuntimeError) -> Self {
+        Failure::Runtime(e)
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Parse(e) => e.fmt(f),
+            Failure::Runtime(e) => e.fmt(f),
+        }
+    }
+}
+
+fn run(env: Rc<Environment>) -> Result<ir::Value, Failure> {
+    let (ir, origins, entrypoint) = {
         // The parser is our main object up until our AST is both finished and type checked
         let mut parser = Parser::new(env.clone());
 
         if let Err(e) = parser.read_prelude_source() {
-            return Err(e.with_parser(parser));
+            return Err(e.with_parser(parser).into());
         }
 
         // Open entry-point sourcecode file
@@ -63,13 +106,16 @@ fn run(env: Rc<Environment>) -> Result<ir::Value, ParseError> {
         // parser.modules.{functions,types} seperated only by headers such as {fn,type,operator}
         let fid = match parser.tokenize(
             FileSource::Project(vec![env.entrypoint_name.clone()]),
-            source_code.chars(),
+            &source_code,
         ) {
             Ok(functions) => functions,
             Err(e) => {
-                return Err(e.with_parser(parser).load_source_code());
+                return Err(e.with_parser(parser).load_source_code().into());
             }
         };
+        if let Err(e) = parser.check_declarations() {
+            return Err(e.with_parser(parser).load_source_code().into());
+        }
         if env.output.ast_full {
             println!(
                 "{}\n",
@@ -87,26 +133,30 @@ fn run(env: Rc<Environment>) -> Result<ir::Value, ParseError> {
         // Verify syntax, infer types and compile to low-level IR.
         match IrBuilder::new(parser, env.clone()).start_type_checker(fid, "main", &[]) {
             Err(e) => {
-                return Err(e.load_source_code());
+                return Err(e.load_source_code().into());
             }
             Ok(ir) => ir,
         }
     };
 
-    let mut runtime = interpreter::Runtime::new(ir);
+    let mut runtime = interpreter::Runtime::new(ir, origins);
     if env.optimize {
         runtime.optimize();
     }
 
     if env.output.ir {
-        for (i, entity) in runtime.instructions.iter().enumerate() {
-            println!("{}: {}", i, entity);
+        for (i, (entity, origin)) in runtime
+            .instructions
+            .iter()
+            .zip(runtime.origins.iter())
+            .enumerate()
+        {
+            println!("{} ({}): {}", i, origin, entity);
         }
     }
-    drop(env);
 
-    let entry = &runtime.instructions[entrypoint];
-    let final_value = interpreter::Runner::start(&runtime, &entry, vec![]);
+    let final_value = interpreter::Runner::start(&runtime, entrypoint as u32, vec![])
+        .map_err(|e| e.load_source_code(&env))?;
     Ok(final_value)
 }
 
@@ -114,6 +164,7 @@ fn run(env: Rc<Environment>) -> Result<ir::Value, ParseError> {
 mod tests {
     use super::*;
     use crate::env::Output;
+    use ir::Value::{Bool, Float, Int};
     use std::path::{Path, PathBuf};
     use std::str::FromStr;
 
@@ -124,7 +175,7 @@ mod tests {
     // STDOUT which gets captured by `gag`.
     //
     // But hey, atleast we can verify that the examples don't panic.
-    fn run_example(path: &str, name: &str) {
+    fn example_environment(path: &str, name: &str) -> Rc<Environment> {
         let environment = Environment {
             leafpath: std::env::var("LEAFPATH")
                 .map(|s| Path::new(&s).to_owned())
@@ -146,9 +197,11 @@ mod tests {
             optimize: true,
         };
 
-        let environment = Rc::new(environment);
+        Rc::new(environment)
+    }
 
-        match run(environment) {
+    fn run_example(path: &str, name: &str) {
+        match run(example_environment(path, name)) {
             Err(e) => {
                 println!("{}", e);
                 panic!("leaf encountered an error")
@@ -157,6 +210,42 @@ mod tests {
         }
     }
 
+    fn run_failing_example(path: &str, name: &str) {
+        match run(example_environment(path, name)) {
+            Err(Failure::Runtime(e)) => println!("{}", e),
+            Err(e) => {
+                println!("{}", e);
+                panic!("leaf failed before running")
+            }
+            Ok(_) => panic!("leaf was expected to fail at runtime"),
+        }
+    }
+
+    fn run_returning(path: &str, name: &str) -> ir::Value {
+        match run(example_environment(path, name)) {
+            Ok(value) => value,
+            Err(e) => {
+                println!("{}", e);
+                panic!("leaf encountered an error")
+            }
+        }
+    }
+
+    fn tuple(entries: Vec<ir::Value>) -> ir::Value {
+        ir::Value::Tuple(Box::new(entries))
+    }
+
+    fn parse_fault(path: &str, name: &str) -> String {
+        match run(example_environment(path, name)) {
+            Err(e @ Failure::Parse(_)) => e.to_string(),
+            Err(e) => {
+                println!("{}", e);
+                panic!("leaf was expected to fail before running")
+            }
+            Ok(_) => panic!("leaf was expected to fail before running"),
+        }
+    }
+
     #[test]
     fn example_lists() {
         run_example("examples/lists.lf", "lists.lf");
@@ -171,4 +260,306 @@ mod tests {
     fn example_void_values() {
         run_example("examples/void-values.lf", "void-values.lf");
     }
+
+    #[test]
+    fn example_strings() {
+        run_example("examples/strings.lf", "strings.lf");
+    }
+
+    #[test]
+    fn example_match() {
+        run_example("examples/match.lf", "match.lf");
+    }
+
+    #[test]
+    fn example_enums() {
+        run_example("examples/enums.lf", "enums.lf");
+    }
+
+    #[test]
+    fn example_generics() {
+        run_example("examples/generics.lf", "generics.lf");
+    }
+
+    #[test]
+    fn example_try() {
+        run_example("examples/try.lf", "try.lf");
+    }
+
+    #[test]
+    fn example_let() {
+        run_example("examples/let.lf", "let.lf");
+    }
+
+    #[test]
+    fn example_closures() {
+        run_example("examples/closures.lf", "closures.lf");
+    }
+
+    #[test]
+    fn example_logic() {
+        run_example("examples/logic.lf", "logic.lf");
+    }
+
+    #[test]
+    fn logic_values() {
+        let value = run_returning("examples/logic-values.lf", "logic-values.lf");
+        let expected = tuple(vec![Bool(true), Bool(false), Bool(true), Bool(true)]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn logic_not_boolean_is_reported() {
+        let fault = parse_fault("examples/logic-not-boolean.lf", "logic-not-boolean.lf");
+        assert!(fault.contains("Wanted `bool` but got `int`"), "{}", fault);
+    }
+
+    #[test]
+    fn example_precedence() {
+        run_example("examples/precedence.lf", "precedence.lf");
+    }
+
+    #[test]
+    fn precedence_values() {
+        let value = run_returning("examples/precedence-values.lf", "precedence-values.lf");
+        let expected = tuple(vec![Int(7), Int(5), Int(512), Int(5), Int(10), Bool(true)]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn precedence_out_of_range_is_reported() {
+        let fault = parse_fault(
+            "examples/precedence-out-of-range.lf",
+            "precedence-out-of-range.lf",
+        );
+        assert!(
+            fault.contains("from 0 to 9 for the operator but got `12`"),
+            "{}",
+            fault
+        );
+    }
+
+    #[test]
+    fn fixity_mismatch_is_reported() {
+        let fault = parse_fault("examples/fixity-mismatch.lf", "fixity-mismatch.lf");
+        assert!(
+            fault.contains("`+` has already been declared with a different precedence"),
+            "{}",
+            fault
+        );
+    }
+
+    #[test]
+    fn example_prefix() {
+        run_example("examples/prefix.lf", "prefix.lf");
+    }
+
+    #[test]
+    fn prefix_values() {
+        let value = run_returning("examples/prefix-values.lf", "prefix-values.lf");
+        let expected = tuple(vec![
+            Int(2),
+            Int(-24),
+            Float(-2.5),
+            Bool(true),
+            Int(11),
+            Int(3),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn prefix_fixity_is_reported() {
+        let fault = parse_fault("examples/prefix-fixity.lf", "prefix-fixity.lf");
+        assert!(
+            fault.contains("`$` takes one parameter so it's a prefix operator"),
+            "{}",
+            fault
+        );
+    }
+
+    #[test]
+    fn example_numbers() {
+        run_example("examples/numbers.lf", "numbers.lf");
+    }
+
+    #[test]
+    fn numbers_values() {
+        let value = run_returning("examples/numbers-values.lf", "numbers-values.lf");
+        let expected = tuple(vec![
+            Float(10.0),
+            Float(9.5),
+            Int(2),
+            Float(-1.5),
+            Int(3),
+            Int(-3),
+            Int(-2),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn builtin_type_mismatch_is_reported() {
+        let fault = parse_fault(
+            "examples/builtin-type-mismatch.lf",
+            "builtin-type-mismatch.lf",
+        );
+        assert!(
+            fault.contains("given a value of type `string`"),
+            "{}",
+            fault
+        );
+    }
+
+    #[test]
+    fn example_compare() {
+        run_example("examples/compare.lf", "compare.lf");
+    }
+
+    #[test]
+    fn compare_values() {
+        let value = run_returning("examples/compare-values.lf", "compare-values.lf");
+        let expected = tuple(vec![
+            tuple(vec![Bool(true), Bool(true)]),
+            tuple(vec![Bool(true), Bool(true)]),
+            tuple(vec![Bool(true), Bool(false)]),
+            tuple(vec![Bool(true), Bool(false), Bool(true)]),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn example_traits() {
+        run_example("examples/traits.lf", "traits.lf");
+    }
+
+    #[test]
+    fn example_tuples() {
+        run_example("examples/tuples.lf", "tuples.lf");
+    }
+
+    #[test]
+    fn example_aliases() {
+        run_example("examples/aliases.lf", "aliases.lf");
+    }
+
+    #[test]
+    fn example_visibility() {
+        run_example("examples/visibility.lf", "visibility.lf");
+    }
+
+    #[test]
+    fn example_modules() {
+        run_example("examples/modules.lf", "modules.lf");
+    }
+
+    #[test]
+    fn example_prelude_imports() {
+        run_example("examples/prelude-imports.lf", "prelude-imports.lf");
+    }
+
+    #[test]
+    fn example_deep() {
+        run_example("examples/deep.lf", "deep.lf");
+    }
+
+    #[test]
+    fn example_exit() {
+        run_example("examples/exit.lf", "exit.lf");
+    }
+
+    #[test]
+    fn example_exit_anywhere() {
+        match run(example_environment("examples/exit-anywhere.lf", "exit-anywhere.lf")) {
+            Ok(ir::Value::Int(3)) => {}
+            Ok(other) => panic!("leaf exited with {} instead of 3", other),
+            Err(e) => {
+                println!("{}", e);
+                panic!("leaf encountered an error")
+            }
+        }
+    }
+
+    #[test]
+    fn status_codes() {
+        assert_eq!(status_code(0), 0);
+        assert_eq!(status_code(3), 3);
+        assert_eq!(status_code(-1), -1);
+        assert_eq!(status_code(256), 1);
+        assert_eq!(status_code(i64::from(i32::MAX) + 1), 1);
+        assert_eq!(status_code(i64::MIN), 1);
+    }
+
+    #[test]
+    fn example_runtime_error() {
+        run_failing_example("examples/runtime-error.lf", "runtime-error.lf");
+    }
+
+    #[test]
+    fn example_overflow() {
+        run_failing_example("examples/overflow.lf", "overflow.lf");
+    }
+
+    #[test]
+    fn example_builtin_values() {
+        run_failing_example("examples/builtin-values.lf", "builtin-values.lf");
+    }
+
+    #[test]
+    fn example_float_to_int() {
+        run_failing_example("examples/float-to-int.lf", "float-to-int.lf");
+    }
+
+    #[test]
+    fn example_records() {
+        run_example("examples/records.lf", "records.lf");
+    }
+
+    #[test]
+    fn example_record_update() {
+        run_example("examples/record-update.lf", "record-update.lf");
+    }
+
+    #[test]
+    fn example_defaults() {
+        run_example("examples/defaults.lf", "defaults.lf");
+    }
+
+    #[test]
+    fn trait_not_implemented_names_type() {
+        let fault = parse_fault(
+            "examples/trait-not-implemented.lf",
+            "trait-not-implemented.lf",
+        );
+        assert!(fault.contains("there's no `impl num shape`"), "{}", fault);
+    }
+
+    #[test]
+    fn comparing_functions_is_rejected() {
+        let fault = parse_fault("examples/compare-functions.lf", "compare-functions.lf");
+        assert!(
+            fault.contains("neither can values of type `maybe<(int -> int)>`"),
+            "{}",
+            fault
+        );
+    }
+
+    #[test]
+    fn comparing_different_types_is_rejected() {
+        let fault = parse_fault(
+            "examples/compare-different-types.lf",
+            "compare-different-types.lf",
+        );
+        assert!(
+            fault.contains("this compares `int` with `string`"),
+            "{}",
+            fault
+        );
+    }
+
+    #[test]
+    fn operator_arity_is_reported() {
+        let fault = parse_fault("examples/operator-arity.lf", "operator-arity.lf");
+        assert!(fault.contains("but `+++` takes 3"), "{}", fault);
+    }
 }
@@ -9,6 +9,7 @@ use std::rc::Rc;
 use termion::{color, color::Fg};
 
 mod tokenizer;
+use tokenizer::TokenSource;
 pub use tokenizer::{Header, Inlinable, Key, RawToken, Token, Tokenizer};
 mod function;
 mod leafmod;
@@ -16,20 +17,22 @@ pub use function::FunctionBuilder;
 
 pub use leafmod::FileSource;
 mod r#type;
-pub use r#type::{CustomType, Enum, MaybeType, Struct, Type};
+use r#type::r#enum::Variant;
+pub use r#type::{Alias, CustomType, Enum, MaybeType, Struct, Type};
 mod ast;
 pub use ast::IrBuilder;
 mod error;
 mod operator;
 pub use error::*;
 pub use leafmod::ParseModule;
+pub use operator::{Associativity, Fixity};
 mod picker;
 mod tracked;
 pub use tracked::Tracked;
 mod attribute;
 pub use attribute::Attr;
 mod identifier;
-pub use identifier::{Identifier, IdentifierType, NAME_CHARS};
+pub use identifier::{Identifier, IdentifierType, NAME_CHARS, OP_CHARS};
 mod annotation;
 pub use annotation::Anot;
 
@@ -82,43 +85,148 @@ impl Parser {
         };
         funcid
     }
+    // Overloads of an operator may repeat its declaration, but can't change it. That includes
+    // overloads of operators from the prelude.
+    fn new_fixity(&mut self, fid: usize, name: &str, fixity: Fixity) -> Result<(), ParseFault> {
+        let existing = self.modules[fid]
+            .fixities
+            .get(name)
+            .or_else(|| self.modules[PRELUDE_FID].fixities.get(name));
+        match existing {
+            Some(existing) if *existing != fixity => {
+                Err(ParseFault::OpFixityMismatch(name.to_string()))
+            }
+            _ => {
+                self.modules[fid].fixities.insert(name.to_string(), fixity);
+                Ok(())
+            }
+        }
+    }
+
+    // Operators are looked up in the module itself and then in the prelude, and so are their
+    // fixities
+    fn fixities_for(&self, fid: usize) -> HashMap<String, Fixity> {
+        let mut fixities = self.modules[PRELUDE_FID].fixities.clone();
+        fixities.extend(
+            self.modules[fid]
+                .fixities
+                .iter()
+                .map(|(k, v)| (k.clone(), *v)),
+        );
+        fixities
+    }
+
+    // Operators may be used before they're declared, so the fixities are gathered from the
+    // operator headers before any function body is parsed
+    fn declare_fixities(&mut self, fid: usize, source_code: &str) -> Result<(), ParseError> {
+        let mut tokenizer = Tokenizer::from(source_code.chars().peekable());
+        while let Some(token) = tokenizer.next() {
+            if token.inner != RawToken::Header(Header::Operator) {
+                continue;
+            }
+            let source_index = token.pos();
+            let funcb = FunctionBuilder::new()
+                .with_header_operator(&mut tokenizer)
+                .map_err(|e| e.fallback_index(source_index).fallback_fid(fid))?;
+            if let Some(fixity) = funcb.fixity {
+                self.new_fixity(fid, &funcb.name.inner.name, fixity)
+                    .map_err(|e| e.into_err(source_index).fallback_fid(fid))?;
+            }
+        }
+        Ok(())
+    }
     fn new_type(
         &mut self,
         fid: usize,
         ident: Anot<Identifier, Type>,
-        fields: Vec<(String, Type)>,
+        mut fields: Vec<(String, Type, usize, Option<Tracked<ast::Entity>>)>,
+        public: bool,
     ) -> usize {
         let module = &mut self.modules[fid];
         let typeid = module.types.len();
-        let (name, type_args) = {
-            // TODO: type_args
-            (ident.inner.name, vec![])
-        };
+        let (name, type_args) = (ident.inner.name, ident.anot);
         module.type_ids.insert(name, typeid);
-        module
-            .types
-            .push(CustomType::Struct(Struct { type_args, fields }));
+        let defaults = fields.iter_mut().map(|(_, _, _, d)| d.take()).collect();
+        let positions = fields.iter().map(|(_, _, pos, _)| *pos).collect();
+        let fields = fields.drain(0..).map(|(name, t, _, _)| (name, t)).collect();
+        module.types.push(CustomType::Struct(Struct {
+            type_args,
+            fields,
+            defaults,
+            positions,
+            public,
+        }));
         typeid
     }
     fn new_enum(
         &mut self,
         fid: usize,
         ident: Anot<Identifier, Type>,
-        fields: HashMap<String, Vec<Type>>,
-    ) -> usize {
+        mut variants: Vec<Variant>,
+        public: bool,
+    ) -> Result<usize, ParseError> {
         let module = &mut self.modules[fid];
         let typeid = module.types.len();
-        let (name, type_args) = {
-            // TODO: type_args
-            (ident.inner.name, vec![])
-        };
+        // Variants are constructed by name alone, so they need to be unique within the module
+        for (name, _, pos) in variants.iter() {
+            let taken = module.types.iter().any(|t| match t {
+                CustomType::Enum(r#enum) => r#enum.get_variant(name).is_some(),
+                CustomType::Struct(_) => false,
+            });
+            if taken {
+                return ParseFault::VariantAlreadyDeclared(name.clone())
+                    .into_err(*pos)
+                    .into();
+            }
+        }
+        let (name, type_args) = (ident.inner.name, ident.anot);
         if module.type_ids.insert(name, typeid).is_some() {
             panic!("ET: Type already exists");
         }
-        module
-            .types
-            .push(CustomType::Enum(Enum { fields, type_args }));
-        typeid
+        let positions = variants.iter().map(|(_, _, pos)| *pos).collect();
+        let fields = variants.drain(0..).map(|(name, t, _)| (name, t)).collect();
+        module.types.push(CustomType::Enum(Enum {
+            fields,
+            positions,
+            type_args,
+            public,
+        }));
+        Ok(typeid)
+    }
+
+    fn new_alias(
+        &mut self,
+        fid: usize,
+        ident: Anot<Identifier, Type>,
+        aliased: Type,
+        pos: usize,
+        public: bool,
+    ) -> Result<(), ParseFault> {
+        let module = &mut self.modules[fid];
+        let (name, type_args) = (ident.inner.name, ident.anot);
+        if module.type_ids.contains_key(&name) || module.aliases.contains_key(&name) {
+            return Err(ParseFault::TypeAlreadyDeclared(name));
+        }
+        module.aliases.insert(
+            name,
+            Alias {
+                aliased,
+                type_args,
+                pos,
+                public,
+            },
+        );
+        Ok(())
+    }
+
+    fn new_trait(&mut self, fid: usize, name: String) -> Result<usize, ParseFault> {
+        let module = &mut self.modules[fid];
+        let traitid = module.trait_ids.len();
+        if module.trait_ids.contains_key(&name) {
+            return Err(ParseFault::TraitAlreadyDeclared(name));
+        }
+        module.trait_ids.insert(name, traitid);
+        Ok(traitid)
     }
 
     // Use leafpath and relative entrypoint path to find all `prelude` folders, and include those
@@ -143,6 +251,7 @@ impl Parser {
         Ok(())
     }
     fn tokenize_prelude(&mut self, path: &Path) -> Result<(), ParseError> {
+        let mut sources = Vec::new();
         for entry in path.read_dir().expect("Couldn't read prelude directory.") {
             let file_path = entry.expect("Prelude file path doesn't exist.").path();
             if file_path.extension() == Some(std::ffi::OsStr::new("lf")) {
@@ -151,40 +260,100 @@ impl Parser {
                     .unwrap()
                     .read_to_string(&mut source_code_buffer)
                     .unwrap();
-
-                self.tokenize(FileSource::Prelude, source_code_buffer.chars())
-                    .map_err(|e| e.fallback_fid(PRELUDE_FID))?;
+                sources.push(source_code_buffer);
             }
         }
+
+        // All prelude files are one module, so operators from any of them can be used in all
+        let fid = self.new_module(FileSource::Prelude);
+        for source_code in sources.iter() {
+            self.declare_fixities(fid, source_code)
+                .map_err(|e| e.fallback_fid(PRELUDE_FID))?;
+        }
+        for source_code in sources.iter() {
+            self.tokenize_declared(FileSource::Prelude, source_code)
+                .map_err(|e| e.fallback_fid(PRELUDE_FID))?;
+        }
         Ok(())
     }
 
     // Turn a specified file into AST and load it into parser
-    pub fn tokenize<I: Iterator<Item = char>>(
+    pub fn tokenize(
+        &mut self,
+        module_path: FileSource,
+        source_code: &str,
+    ) -> Result<usize, ParseError> {
+        let fid = self.new_module(module_path.clone());
+        self.declare_fixities(fid, source_code)?;
+        self.tokenize_declared(module_path, source_code)
+    }
+
+    // Same as `tokenize` but for modules that already had their fixities declared
+    fn tokenize_declared(
         &mut self,
         module_path: FileSource,
-        source_code: I,
+        source_code: &str,
     ) -> Result<usize, ParseError> {
         let fid = self.new_module(module_path.clone());
+        let mut tokenizer = Tokenizer::from(source_code.chars().peekable());
+        let result = self.tokenize_items(fid, &module_path, &mut tokenizer);
+        // A malformed literal ends the token stream early, so the fault is what's worth reporting
+        // rather than what the early end caused.
+        match tokenizer.fault.take() {
+            Some(e) => Err(e.fallback_fid(fid)),
+            None => result,
+        }
+    }
 
-        let mut tokenizer = Tokenizer::from(source_code.peekable());
+    fn tokenize_items<I: Iterator<Item = char>>(
+        &mut self,
+        fid: usize,
+        module_path: &FileSource,
+        tokenizer: &mut Tokenizer<I>,
+    ) -> Result<usize, ParseError> {
+        let fixities = self.fixities_for(fid);
+        // Set by a `pub` for the header that follows it
+        let mut next_public = false;
         loop {
             let token = match tokenizer.next() {
                 Some(t) => t,
                 None => return Ok(fid),
             };
             let source_index = token.pos();
+            let public = std::mem::take(&mut next_public);
             match token.inner {
                 RawToken::Header(h) => match h {
+                    Header::Pub => match tokenizer.peek().map(|t| &t.inner) {
+                        Some(RawToken::Header(
+                            Header::Function
+                            | Header::Operator
+                            | Header::Type
+                            | Header::Enum
+                            | Header::Alias,
+                        )) => next_public = true,
+                        Some(other) => {
+                            return ParseFault::PubOnNonItem(other.clone())
+                                .into_err(source_index)
+                                .fallback_fid(fid)
+                                .into()
+                        }
+                        None => {
+                            return ParseFault::EndedWhileExpecting(vec!["fn".into()])
+                                .into_err(source_index)
+                                .fallback_fid(fid)
+                                .into()
+                        }
+                    },
                     Header::Function => {
                         let mut funcb = FunctionBuilder::new()
-                            .with_header(&mut tokenizer)
+                            .with_header(tokenizer)
                             .map_err(|e| e.fallback_fid(fid).fallback_index(source_index))?;
+                        funcb.public = public;
                         if funcb.name.anot.is_empty()
                             || funcb.name.anot.iter().any(|attr| attr.is_targeted_sys())
                         {
                             funcb
-                                .parse_body(&mut tokenizer)
+                                .parse_body(tokenizer, &fixities)
                                 .map_err(|e| e.fallback_index(source_index).fallback_fid(fid))?;
 
                             self.new_function(fid, funcb);
@@ -197,22 +366,88 @@ impl Parser {
                     }
                     Header::Operator => {
                         let mut funcb =
-                            FunctionBuilder::new().with_header_operator(&mut tokenizer)?;
+                            FunctionBuilder::new().with_header_operator(tokenizer)?;
+                        funcb.public = public;
                         funcb
-                            .parse_body(&mut tokenizer)
+                            .parse_body(tokenizer, &fixities)
                             .map_err(|e| e.fallback_index(source_index).fallback_fid(fid))?;
 
                         self.new_function(fid, funcb);
                     }
                     Header::Type => {
-                        let (type_name, fields) = r#type::r#struct::parse(&mut tokenizer)?;
+                        let (type_name, fields) = r#type::r#struct::parse(tokenizer, &fixities)
+                            .map_err(|e| e.fallback_fid(fid))?;
 
-                        self.new_type(fid, type_name, fields);
+                        self.new_type(fid, type_name, fields, public);
                     }
                     Header::Enum => {
-                        let (type_name, variants) = r#type::r#enum::parse(&mut tokenizer)?;
+                        let (type_name, variants) =
+                            r#type::r#enum::parse(tokenizer).map_err(|e| e.fallback_fid(fid))?;
 
-                        self.new_enum(fid, type_name, variants);
+                        self.new_enum(fid, type_name, variants, public)
+                            .map_err(|e| e.fallback_fid(fid))?;
+                    }
+                    Header::Alias => {
+                        let (name, aliased, pos) =
+                            r#type::alias::parse(tokenizer).map_err(|e| e.fallback_fid(fid))?;
+
+                        self.new_alias(fid, name, aliased, pos, public)
+                            .map_err(|e| e.into_err(source_index).fallback_fid(fid))?;
+                    }
+                    Header::Trait => {
+                        let (name, pos) = match tokenizer.next().map(|t| t.sep()) {
+                            Some((RawToken::Identifier(ident), pos)) => (ident.inner.name, pos),
+                            Some((other, pos)) => {
+                                return ParseFault::GotButExpected(other, vec!["trait name".into()])
+                                    .into_err(pos)
+                                    .fallback_fid(fid)
+                                    .into()
+                            }
+                            None => {
+                                return ParseFault::EndedWhileExpecting(vec!["trait name".into()])
+                                    .into_err(tokenizer.position - 1)
+                                    .fallback_fid(fid)
+                                    .into()
+                            }
+                        };
+                        self.new_trait(fid, name)
+                            .map_err(|e| e.into_err(pos).fallback_fid(fid))?;
+                    }
+                    Header::Impl => {
+                        let r#trait = match tokenizer.next().map(|t| t.sep()) {
+                            Some((RawToken::Identifier(ident), _)) => ident.inner,
+                            Some((other, pos)) => {
+                                return ParseFault::GotButExpected(other, vec!["trait name".into()])
+                                    .into_err(pos)
+                                    .fallback_fid(fid)
+                                    .into()
+                            }
+                            None => {
+                                return ParseFault::EndedWhileExpecting(vec!["trait name".into()])
+                                    .into_err(tokenizer.position - 1)
+                                    .fallback_fid(fid)
+                                    .into()
+                            }
+                        };
+                        let t = match tokenizer.next().map(|t| t.sep()) {
+                            Some((RawToken::Identifier(ident), pos)) => {
+                                Type::try_from(ident.to_string().as_str())
+                                    .map_err(|e| e.into_err(pos).fallback_fid(fid))?
+                            }
+                            Some((other, pos)) => {
+                                return ParseFault::GotButExpected(other, vec!["type".into()])
+                                    .into_err(pos)
+                                    .fallback_fid(fid)
+                                    .into()
+                            }
+                            None => {
+                                return ParseFault::EndedWhileExpecting(vec!["type".into()])
+                                    .into_err(tokenizer.position - 1)
+                                    .fallback_fid(fid)
+                                    .into()
+                            }
+                        };
+                        self.modules[fid].impls.push((r#trait, t, source_index));
                     }
                     Header::Use => {
                         let ident = match tokenizer.next().map(|t| t.inner) {
@@ -226,11 +461,50 @@ impl Parser {
                                 panic!("ET: Unexpected thing after `use` keyword: {:?}", other)
                             }
                         };
-                        let name = ident.inner.name.clone();
+                        // `use std:list as l`
+                        let name = match tokenizer.peek().map(|t| &t.inner) {
+                            Some(RawToken::Identifier(r#as)) if r#as.inner.name == "as" => {
+                                tokenizer.next();
+                                match tokenizer.next().map(|t| t.sep()) {
+                                    Some((RawToken::Identifier(alias), pos)) => {
+                                        if !alias.inner.path.is_empty() {
+                                            let mut entries = alias.inner.path;
+                                            entries.push(alias.inner.name);
+                                            return ParseFault::InvalidPath(entries)
+                                                .into_err(pos)
+                                                .fallback_fid(fid)
+                                                .into();
+                                        }
+                                        alias.inner.name
+                                    }
+                                    Some((other, pos)) => {
+                                        return ParseFault::GotButExpected(
+                                            other,
+                                            vec!["module alias".into()],
+                                        )
+                                        .into_err(pos)
+                                        .fallback_fid(fid)
+                                        .into()
+                                    }
+                                    None => {
+                                        return ParseFault::EndedWhileExpecting(vec![
+                                            "module alias".into(),
+                                        ])
+                                        .into_err(tokenizer.position - 1)
+                                        .fallback_fid(fid)
+                                        .into()
+                                    }
+                                }
+                            }
+                            _ => ident.inner.name.clone(),
+                        };
+                        let full_path = ident.inner.to_string();
                         let ident = ident
                             .try_map_anot(|s| Type::try_from(s.as_str()))
                             .map_err(|e| e.into_err(source_index))?;
-                        let file_path = module_path.fork_from(ident, &*self.environment);
+                        let file_path = module_path
+                            .fork_from(ident, &self.environment)
+                            .map_err(|e| e.into_err(source_index).fallback_fid(fid))?;
 
                         let usefid = self
                             .tokenize_import(file_path)
@@ -239,7 +513,20 @@ impl Parser {
                         // `usefid` is the ID which was assigned,
                         // it's already been inserted as a module in the parser
                         // but we need to add it to *this* modules imports.
-                        self.modules[fid].imports.insert(name, usefid);
+                        let imports = &mut self.modules[fid].imports;
+                        for bound in [name, full_path].iter() {
+                            match imports.get(bound) {
+                                Some(existing) if *existing != usefid => {
+                                    return ParseFault::ModuleAliasTaken(bound.clone())
+                                        .into_err(source_index)
+                                        .fallback_fid(fid)
+                                        .into()
+                                }
+                                _ => {
+                                    imports.insert(bound.clone(), usefid);
+                                }
+                            }
+                        }
                     }
                 },
                 RawToken::NewLine => continue,
@@ -249,7 +536,16 @@ impl Parser {
                         source_index,
                         ParseFault::GotButExpected(
                             token.inner,
-                            vec!["fn".into(), "type".into(), "enum".into(), "where".into()],
+                            vec![
+                                "fn".into(),
+                                "type".into(),
+                                "enum".into(),
+                                "trait".into(),
+                                "impl".into(),
+                                "alias".into(),
+                                "pub".into(),
+                                "where".into(),
+                            ],
                         ),
                     )
                     .fallback_fid(fid)
@@ -260,6 +556,12 @@ impl Parser {
     }
 
     fn tokenize_import(&mut self, file_path: FileSource) -> Result<usize, ParseError> {
+        // Modules used from several places, such as from both the prelude and the project, are
+        // only tokenized the first time.
+        if let Some(fid) = self.module_ids.get(&file_path) {
+            return Ok(*fid);
+        }
+
         let pathbuf = file_path.to_pathbuf(&self.environment);
         let mut source_code = String::with_capacity(20);
         File::open(pathbuf.clone())
@@ -268,7 +570,7 @@ impl Parser {
             .map_err(|e| ParseFault::ModuleLoadFailed(pathbuf, e.kind()).into_err(0))?;
 
         // Fork and tokenize this module first instead.
-        let usefid = self.tokenize(file_path, source_code.chars())?;
+        let usefid = self.tokenize(file_path, &source_code)?;
         Ok(usefid)
     }
 
@@ -277,16 +579,7 @@ impl Parser {
         self_fid: usize,
         ident: &Anot<Identifier, Type>,
     ) -> Result<(&HashMap<Vec<Type>, usize>, usize), ParseFault> {
-        let fid = {
-            match ident.inner.path.len() {
-                0 => self_fid,
-                1 => {
-                    let mod_name = &ident.inner.path[0];
-                    self.modules[self_fid].get_import(mod_name)?
-                }
-                _ => return Err(ParseFault::InvalidPath(ident.inner.path.clone())),
-            }
-        };
+        let fid = self.fid_from_path(self_fid, &ident.inner.path)?;
 
         match self.modules[fid].function_ids.get(&ident.inner.name) {
             Some(variants) => Ok((variants, fid)),
@@ -306,25 +599,156 @@ impl Parser {
         }
     }
 
-    pub fn destruct_custom_type(&self, self_fid: usize, t: Type) -> Type {
-        if let Type::Custom(ident) = t {
-            dbg!(&ident, self_fid);
-            let fid = match ident.inner.path.last() {
-                None => self_fid,
-                Some(modname) => self.modules[self_fid]
-                    .imports
-                    .get(modname)
-                    .cloned()
-                    .expect("ET"),
+    // Declarations may refer to traits and types declared later or in modules imported later, so
+    // the types they mention are checked once every module has been tokenized.
+    pub fn check_declarations(&self) -> Result<(), ParseError> {
+        // Resolving an alias that refers back to itself would never end, so those are ruled out
+        // before any types are resolved
+        for (fid, module) in self.modules.iter().enumerate() {
+            for (name, alias) in module.aliases.iter() {
+                let mut visiting = vec![(fid, name.clone())];
+                self.check_alias_cycle(fid, &alias.aliased, &mut visiting)
+                    .map_err(|e| e.into_err(alias.pos).fallback_fid(fid))?;
+            }
+        }
+        for (fid, module) in self.modules.iter().enumerate() {
+            let check = |t: &Type, pos: usize| {
+                self.destruct_custom_type(fid, t.clone())
+                    .map(drop)
+                    .map_err(|e| e.into_err(pos).fallback_fid(fid))
             };
-            let tid = self.modules[fid]
-                .type_ids
-                .get(&ident.inner.name)
-                .cloned()
-                .expect("ET");
-            Type::KnownCustom(fid, tid)
-        } else {
-            t
+            for (r#trait, t, pos) in module.impls.iter() {
+                self.find_trait(fid, r#trait)
+                    .map_err(|e| e.into_err(*pos).fallback_fid(fid))?;
+                check(t, *pos)?;
+            }
+            for alias in module.aliases.values() {
+                check(&alias.aliased, alias.pos)?;
+            }
+            for custom in module.types.iter() {
+                match custom {
+                    CustomType::Struct(r#struct) => {
+                        for ((_, t), pos) in r#struct.fields.iter().zip(&r#struct.positions) {
+                            check(t, *pos)?;
+                        }
+                    }
+                    CustomType::Enum(r#enum) => {
+                        for ((_, payload), pos) in r#enum.fields.iter().zip(&r#enum.positions) {
+                            payload.iter().try_for_each(|t| check(t, *pos))?;
+                        }
+                    }
+                }
+            }
+            for func in module.functions.iter() {
+                let types = func
+                    .parameter_types
+                    .iter()
+                    .chain(std::iter::once(&func.returns));
+                for (i, t) in types.enumerate() {
+                    check(t, func.type_positions.get(i).copied().unwrap_or(0))?;
+                }
+                for (_, r#trait, pos) in func.constraints.iter() {
+                    self.find_trait(fid, r#trait)
+                        .map_err(|e| e.into_err(*pos).fallback_fid(fid))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // `visiting` are the aliases that led to `t`, which may not be reached again
+    fn check_alias_cycle(
+        &self,
+        self_fid: usize,
+        t: &Type,
+        visiting: &mut Vec<(usize, String)>,
+    ) -> Result<(), ParseFault> {
+        match t {
+            Type::Custom(ident) => {
+                // Aliases that can't be found are reported once the type is resolved instead
+                if let Ok(Some((fid, alias))) = self.find_alias(self_fid, ident) {
+                    let key = (fid, ident.inner.name.clone());
+                    if visiting.contains(&key) {
+                        return Err(ParseFault::AliasCycle(ident.inner.name.clone()));
+                    }
+                    visiting.push(key);
+                    self.check_alias_cycle(fid, &alias.aliased, visiting)?;
+                    visiting.pop();
+                }
+                ident
+                    .anot
+                    .iter()
+                    .try_for_each(|t| self.check_alias_cycle(self_fid, t, visiting))
+            }
+            Type::List(box inner) => self.check_alias_cycle(self_fid, inner, visiting),
+            Type::Tuple(entries) => entries
+                .iter()
+                .try_for_each(|t| self.check_alias_cycle(self_fid, t, visiting)),
+            Type::Function(box (takes, gives)) => takes
+                .iter()
+                .chain(std::iter::once(gives))
+                .try_for_each(|t| self.check_alias_cycle(self_fid, t, visiting)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn destruct_custom_type(&self, self_fid: usize, t: Type) -> Result<Type, ParseFault> {
+        match t {
+            Type::Custom(ident) => {
+                if let Some((fid, alias)) = self.find_alias(self_fid, &ident)? {
+                    if ident.anot.len() != alias.type_args.len() {
+                        return Err(ParseFault::TypeArgAmountMismatch(
+                            ident,
+                            alias.type_args.len(),
+                        ));
+                    }
+                    let type_args = ident
+                        .anot
+                        .into_iter()
+                        .map(|t| self.destruct_custom_type(self_fid, t))
+                        .collect::<Result<Vec<Type>, ParseFault>>()?;
+                    let generics = alias
+                        .type_args
+                        .iter()
+                        .zip(type_args)
+                        .filter_map(|(param, arg)| match param {
+                            Type::Generic(n) => Some((*n, arg)),
+                            _ => None,
+                        })
+                        .collect();
+                    return Ok(self
+                        .destruct_custom_type(fid, alias.aliased.clone())?
+                        .decoded(&generics));
+                }
+                let (fid, tid) = self.find_type(self_fid, &ident)?;
+                let expected = self.modules[fid].types[tid].type_args().len();
+                if ident.anot.len() != expected {
+                    return Err(ParseFault::TypeArgAmountMismatch(ident, expected));
+                }
+                let type_args = ident
+                    .anot
+                    .into_iter()
+                    .map(|t| self.destruct_custom_type(self_fid, t))
+                    .collect::<Result<Vec<Type>, ParseFault>>()?;
+                Ok(Type::KnownCustom(fid, tid, type_args))
+            }
+            Type::List(box inner) => Ok(Type::List(Box::new(
+                self.destruct_custom_type(self_fid, inner)?,
+            ))),
+            Type::Tuple(entries) => Ok(Type::Tuple(
+                entries
+                    .into_iter()
+                    .map(|t| self.destruct_custom_type(self_fid, t))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Type::Function(box (takes, gives)) => Ok(Type::Function(Box::new((
+                takes
+                    .into_iter()
+                    .map(|t| self.destruct_custom_type(self_fid, t))
+                    .collect::<Result<_, _>>()?,
+                self.destruct_custom_type(self_fid, gives)?,
+            )))),
+            _ => Ok(t),
         }
     }
 }
@@ -96,13 +96,28 @@ impl TryFrom<&str> for Anot<String, String> {
 
 fn get_anots<I: Iterator<Item = char>>(iter: &mut I) -> Result<VecDeque<String>, ParseFault> {
     let mut this_anot = String::new();
+    // Nested annotations such as `box<maybe<int>>` and tuples such as `box<(int, int)>` are kept
+    // intact for the type parser
+    let mut depth = 0;
     while let Some(c) = iter.next() {
         match c {
+            '<' | '(' => {
+                depth += 1;
+                this_anot.push(c);
+            }
+            ')' => {
+                depth -= 1;
+                this_anot.push(c);
+            }
+            '>' if depth > 0 => {
+                depth -= 1;
+                this_anot.push(c);
+            }
             '>' => {
                 let anots = VecDeque::from(vec![this_anot.trim().to_owned()]);
                 return Ok(anots);
             }
-            ',' => {
+            ',' if depth == 0 => {
                 let mut nested = get_anots(iter)?;
                 nested.push_front(this_anot.trim().to_owned());
                 return Ok(nested);
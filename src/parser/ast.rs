@@ -1,23 +1,32 @@
 use crate::env::Environment;
 use crate::ir;
 use crate::parser::{Anot, Identifier, MaybeType, ParseError, Parser, Type};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
 
 mod checker;
 mod entity;
-pub use entity::{Callable, Entity, Passable};
+pub use entity::{Callable, Entity, Passable, Pattern};
 mod builder;
 pub use builder::AstBuilder;
 mod metainfo;
-pub use metainfo::{IdentMeta, Identifiable, Meta};
+pub use metainfo::{Capture, IdentMeta, Identifiable, Meta};
+
+// The type a struct field default evaluates to along with its instructions
+type FieldDefault = (Type, ir::Entity);
 
 pub struct IrBuilder {
     parser: Parser,
     completed: RefCell<Vec<ir::Entity>>,
+    // Indexed the same as `completed`
+    origins: RefCell<Vec<ir::Origin>>,
     environment: Rc<Environment>,
     assigned_indexes: RefCell<HashMap<Meta, usize>>,
+    next_index: Cell<usize>,
+    // Struct field defaults by their module, type and field index. They're checked up front by
+    // `check_defaults` and then reused by every record that leaves the field out.
+    defaults: RefCell<HashMap<(usize, usize, usize), FieldDefault>>,
 }
 
 impl IrBuilder {
@@ -26,7 +35,10 @@ impl IrBuilder {
             parser,
             environment: env,
             assigned_indexes: RefCell::default(),
+            next_index: Cell::new(0),
+            defaults: RefCell::default(),
             completed: RefCell::new(Vec::with_capacity(5)),
+            origins: RefCell::new(Vec::with_capacity(5)),
         }
     }
 
@@ -35,19 +47,47 @@ impl IrBuilder {
         match map.get_mut(meta) {
             Some(i) => *i,
             None => {
-                let i = map.len();
+                let i = self.next_index.get();
+                self.next_index.set(i + 1);
                 map.insert(meta.clone(), i);
                 i
             }
         }
     }
 
+    // Closure bodies become instructions of their own, so they're called by index like functions
+    pub fn complete_closure(&self, entity: ir::Entity, meta: &Meta, source_index: usize) -> u32 {
+        let i = self.next_index.get();
+        self.next_index.set(i + 1);
+        self.complete(i, entity);
+        self.record_origin(i, meta, source_index, true);
+        i as u32
+    }
+
+    pub fn record_origin(&self, findex: usize, meta: &Meta, source_index: usize, lambda: bool) {
+        let origin = ir::Origin {
+            name: meta.ident.inner.name.clone(),
+            operator: meta.ident.inner.is_operator(),
+            lambda,
+            module: self.parser.modules[meta.fid].module_path.clone(),
+            source_index,
+        };
+        let mut origins = self.origins.borrow_mut();
+        if findex >= origins.len() {
+            origins.resize(findex + 1, ir::Origin::default());
+        }
+        origins[findex] = origin;
+    }
+
     pub fn start_type_checker(
         self,
         fid: usize,
         name: &str,
         params: &[MaybeType],
-    ) -> Result<(Vec<ir::Entity>, usize), ParseError> {
+    ) -> Result<(Vec<ir::Entity>, Vec<ir::Origin>, usize), ParseError> {
+        if let Err(e) = self.check_defaults() {
+            return Err(e.with_parser(self.parser));
+        }
         let (_returns, assigned_index) = match self.find_and_build_function(
             fid,
             &Anot::from((Identifier::raw(name), vec![])),
@@ -56,7 +96,11 @@ impl IrBuilder {
             Ok(a) => a,
             Err(e) => return Err(e.with_parser(self.parser)),
         };
-        Ok((self.completed.into_inner(), assigned_index))
+        Ok((
+            self.completed.into_inner(),
+            self.origins.into_inner(),
+            assigned_index,
+        ))
     }
 
     pub fn complete(&self, findex: usize, entity: ir::Entity) {
@@ -1,18 +1,57 @@
-use super::{Callable, Entity, Passable};
+use super::{Callable, Entity, Passable, Pattern};
 use crate::parser::tokenizer::TokenSource;
 use crate::parser::{
-    Anot, Identifier, IdentifierType, Key, ParseError, ParseFault, RawToken, Tokenizer, Tracked,
-    Type,
+    Anot, Associativity, Fixity, Identifier, IdentifierType, Inlinable, Key, ParseError,
+    ParseFault, RawToken, Tokenizer, Tracked, Type,
 };
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::mem;
+
+// `and` binds tighter than `or` and both of them are looser than any operator. The precedence
+// declared by operators is added on top of `OPERATOR_PRECEDENCE`.
+const OR_PRECEDENCE: u8 = 1;
+const AND_PRECEDENCE: u8 = 2;
+const OPERATOR_PRECEDENCE: u8 = 3;
+const PREFIX_PRECEDENCE: u8 = u8::MAX;
 
 pub struct AstBuilder<'a, I: Iterator<Item = char>> {
     tokenizer: &'a mut Tokenizer<I>,
+    fixities: &'a HashMap<String, Fixity>,
+
+    // When set, parameters and pipes aren't looked for on the following lines
+    single_line: bool,
+
+    // Anything binding looser than this is left for the enclosing expression to pick up
+    min_precedence: u8,
+    // Inside of `first` statements `and` separates the statements instead
+    in_first: bool,
 }
 
 impl<'a, I: Iterator<Item = char>> AstBuilder<'a, I> {
-    pub fn new(tokenizer: &'a mut Tokenizer<I>) -> Self {
-        Self { tokenizer }
+    pub fn new(tokenizer: &'a mut Tokenizer<I>, fixities: &'a HashMap<String, Fixity>) -> Self {
+        Self {
+            tokenizer,
+            fixities,
+            single_line: false,
+            min_precedence: 0,
+            in_first: false,
+        }
+    }
+
+    // Used for expressions that are followed by more declarations, such as default values of
+    // struct fields
+    pub fn single_line(
+        tokenizer: &'a mut Tokenizer<I>,
+        fixities: &'a HashMap<String, Fixity>,
+    ) -> Self {
+        Self {
+            tokenizer,
+            fixities,
+            single_line: true,
+            min_precedence: 0,
+            in_first: false,
+        }
     }
 }
 
@@ -23,14 +62,16 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
             Some(t) => t,
             None => return Err(ParseFault::EmptyParen.into_err(0)),
         };
-        match t.inner {
+        match &t.inner {
             RawToken::Header(_) | RawToken::Key(Key::Where) => {
                 Err(ParseFault::EmptyParen.into_err(t.pos()))
             }
             RawToken::Key(Key::ParenOpen) => {
                 let paren_pos = t.pos();
                 self.tokenizer.next();
-                let v = self.run_chunk().map_err(|e| e.fallback_index(paren_pos))?;
+                let v = self
+                    .run_delimited()
+                    .map_err(|e| e.fallback_index(paren_pos))?;
                 let after = self.tokenizer.next();
                 match after.map(|a| a.inner) {
                     Some(RawToken::Key(Key::ParenClose)) => {
@@ -42,9 +83,15 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                                 Tracked::new(Callable::Lambda(params, body)).set(pos),
                             )
                         } else {
+                            let v = self.run_maybe_field_access(v)?;
                             self.run_maybe_operator(v)
                         }
                     }
+                    Some(RawToken::Key(Key::Comma)) => {
+                        let v = self.run_tuple(v, paren_pos)?;
+                        let v = self.run_maybe_field_access(Tracked::new(v).set(paren_pos))?;
+                        self.run_maybe_operator(v)
+                    }
                     _ => Err(ParseFault::Unmatched(Key::ParenOpen).into_err(paren_pos)),
                 }
             }
@@ -76,6 +123,30 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                     .map_err(|e| e.fallback_index(pos))?;
                 self.run_maybe_operator(Tracked::new(v).set(pos))
             }
+            RawToken::Key(Key::Match) => {
+                let pos = t.pos();
+                self.tokenizer.next();
+                let v = self.run_match().map_err(|e| e.fallback_index(pos))?;
+                Ok(Tracked::new(v).set(pos))
+            }
+            RawToken::Key(Key::Try) => {
+                let pos = t.pos();
+                self.tokenizer.next();
+                let v = self.run_chunk().map_err(|e| e.fallback_index(pos))?;
+                Ok(Tracked::new(Entity::Try(Box::new(v))).set(pos))
+            }
+            RawToken::Key(Key::PrimitiveExit) => {
+                let pos = t.pos();
+                self.tokenizer.next();
+                let v = self.run_chunk().map_err(|e| e.fallback_index(pos))?;
+                Ok(Tracked::new(Entity::Exit(Box::new(v))).set(pos))
+            }
+            RawToken::Key(Key::Let) => {
+                let pos = t.pos();
+                self.tokenizer.next();
+                let v = self.run_let().map_err(|e| e.fallback_index(pos))?;
+                Ok(Tracked::new(v).set(pos))
+            }
             RawToken::Key(Key::First) => {
                 let pos = t.pos();
                 self.tokenizer.next();
@@ -84,11 +155,26 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                     .map_err(|e| e.fallback_index(pos))?;
                 self.run_maybe_operator(Tracked::new(v).set(pos))
             }
+            RawToken::Identifier(ident) if ident.inner.is_operator() => {
+                let (ident, pos) = assume!(RawToken::Identifier, self.tokenizer.next());
+                let ident = ident
+                    .try_map_anot(|s| Type::try_from(s.as_str()))
+                    .map_err(|e| e.into_err(pos))?;
+                let v = self
+                    .run_prefix(Tracked::new(ident).set(pos))
+                    .map_err(|e| e.fallback_index(pos))?;
+                self.run_maybe_operator(v)
+            }
             RawToken::Identifier(_) => {
                 let (ident, pos) = assume!(RawToken::Identifier, self.tokenizer.next());
                 let mut ident = ident
                     .try_map_anot(|s| Type::try_from(s.as_str()))
                     .map_err(|e| e.into_err(pos))?;
+                if let Some(RawToken::Key(Key::Dot)) = self.tokenizer.peek().map(|t| &t.inner) {
+                    let v = Tracked::new(Entity::SingleIdent(ident)).set(pos);
+                    let v = self.run_maybe_field_access(v)?;
+                    return self.run_maybe_operator(v);
+                }
                 let callable = if ident.inner.path.first().map(|s| s.as_str()) == Some("builtin") {
                     ident.inner.path.remove(0);
                     Callable::Builtin(ident)
@@ -134,11 +220,22 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
         }
     }
 
+    // Runs a chunk that ends with something other than an operator, such as `)` or `then`. So
+    // everything up until that point belongs to it.
+    fn run_delimited(&mut self) -> Result<Tracked<Entity>, ParseError> {
+        let min_precedence = mem::replace(&mut self.min_precedence, 0);
+        let in_first = mem::replace(&mut self.in_first, false);
+        let v = self.run_chunk();
+        self.min_precedence = min_precedence;
+        self.in_first = in_first;
+        v
+    }
+
     // edge-case where lambdas don't require () to take pipe after as parameter
     fn lambda_should_consume_pipe(&mut self) -> bool {
         match self.tokenizer.peek().map(|t| &t.inner) {
             Some(RawToken::Key(Key::Pipe)) => true,
-            Some(RawToken::NewLine) => {
+            Some(RawToken::NewLine) if !self.single_line => {
                 self.tokenizer.next();
                 self.lambda_should_consume_pipe()
             }
@@ -148,6 +245,7 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
 
     fn run_lambda(&mut self) -> Result<Tracked<Entity>, ParseError> {
         let mut params = Vec::new();
+        let mut destructured = Vec::new();
         let pos = loop {
             match self.tokenizer.next().map(|t| t.sep()) {
                 Some((RawToken::Identifier(ident), pos)) => {
@@ -156,6 +254,17 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                         .map_err(|e| e.into_err(pos))?;
                     params.push(ident)
                 }
+                // `\(a, b) -> a` takes the tuple as an unnameable parameter and matches on it
+                Some((RawToken::Key(Key::ParenOpen), pos)) => {
+                    let pattern = self.run_paren_pattern(pos)?;
+                    let ident = Anot::new(Identifier {
+                        path: Vec::new(),
+                        name: pattern.to_string(),
+                        kind: IdentifierType::Normal,
+                    });
+                    params.push(ident.clone());
+                    destructured.push((ident, pattern));
+                }
                 Some((RawToken::Key(Key::Arrow), pos)) => break pos,
                 Some((other, pos)) => {
                     return Err(ParseFault::GotButExpected(
@@ -169,7 +278,12 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                 }
             }
         };
-        let v = self.run_chunk()?;
+        let mut v = self.run_chunk()?;
+        while let Some((ident, pattern)) = destructured.pop() {
+            let value = Tracked::new(Entity::SingleIdent(ident)).set(pattern.pos());
+            let body_pos = v.pos();
+            v = Tracked::new(Entity::Match(Box::new(value), vec![(pattern, v)])).set(body_pos);
+        }
 
         Ok(Tracked::new(Entity::Lambda(params, Box::new(v))).set(pos))
     }
@@ -197,15 +311,25 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                     .try_map_anot(|s| Type::try_from(s.as_str()))
                     .map_err(|e| e.into_err(pos))?;
                 let v = Tracked::new(Entity::SingleIdent(ident)).set(pos);
+                let v = self.run_maybe_field_access(v)?;
                 let mut params = self.run_parameterized()?;
                 params.insert(0, v);
                 Ok(params)
             }
             RawToken::Key(Key::ParenOpen) => {
+                let paren_pos = t.pos();
                 self.tokenizer.next();
-                let v = self.run_chunk()?;
+                let v = self.run_delimited()?;
                 match self.tokenizer.next().map(|a| a.sep()) {
                     Some((RawToken::Key(Key::ParenClose), _pos)) => {
+                        let v = self.run_maybe_field_access(v)?;
+                        let mut params = self.run_parameterized()?;
+                        params.insert(0, v);
+                        Ok(params)
+                    }
+                    Some((RawToken::Key(Key::Comma), _pos)) => {
+                        let v = self.run_tuple(v, paren_pos)?;
+                        let v = self.run_maybe_field_access(Tracked::new(v).set(paren_pos))?;
                         let mut params = self.run_parameterized()?;
                         params.insert(0, v);
                         Ok(params)
@@ -247,8 +371,13 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
             | RawToken::Key(Key::Then)
             | RawToken::Key(Key::Else)
             | RawToken::Key(Key::ListClose)
+            | RawToken::Key(Key::Comma)
             | RawToken::Key(Key::And)
+            | RawToken::Key(Key::Or)
+            | RawToken::Key(Key::Bar)
+            | RawToken::Key(Key::In)
             | RawToken::Key(Key::Elif) => Ok(Vec::new()),
+            RawToken::NewLine if self.single_line => Ok(Vec::new()),
             RawToken::NewLine => {
                 self.tokenizer.next();
                 self.run_parameterized()
@@ -266,7 +395,7 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
         };
         match inner {
             RawToken::Key(Key::ParenOpen) => {
-                let (entity, pos) = self.run_chunk()?.sep();
+                let (entity, pos) = self.run_delimited()?.sep();
                 match self.tokenizer.next().map(|t| t.sep()) {
                     Some((RawToken::Key(Key::ParenClose), _)) => {}
                     Some((other, pos)) => {
@@ -319,9 +448,10 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                 | RawToken::Key(Key::Pipe)
                 | RawToken::Key(Key::ParenOpen)
                 | RawToken::Key(Key::ClosureMarker)
+                | RawToken::Key(Key::RecordOpen)
                 | RawToken::Key(Key::ListOpen) => true,
                 RawToken::Identifier(ident) => !ident.inner.is_operator(),
-                RawToken::NewLine => {
+                RawToken::NewLine if !self.single_line => {
                     self.tokenizer.next();
                     self.next_can_be_parameter()
                 }
@@ -331,6 +461,32 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
         }
     }
 
+    // `p.name`, may be chained such as `p.name.first`
+    fn run_maybe_field_access(
+        &mut self,
+        mut record: Tracked<Entity>,
+    ) -> Result<Tracked<Entity>, ParseError> {
+        while let Some(RawToken::Key(Key::Dot)) = self.tokenizer.peek().map(|t| &t.inner) {
+            let dot_pos = self.tokenizer.next().unwrap().pos();
+            match self.tokenizer.next().map(|t| t.sep()) {
+                Some((RawToken::Identifier(ident), pos)) => {
+                    record = Tracked::new(Entity::FieldAccess(Box::new(record), ident.inner.name))
+                        .set(pos);
+                }
+                Some((other, pos)) => {
+                    return Err(
+                        ParseFault::GotButExpected(other, vec!["field name".into()]).into_err(pos)
+                    )
+                }
+                None => {
+                    return Err(ParseFault::EndedWhileExpecting(vec!["field name".into()])
+                        .into_err(dot_pos))
+                }
+            }
+        }
+        Ok(record)
+    }
+
     // We run this when there *might* be an operator coming. If there isn't then we just return the
     // left argument for the nonexistant operator.
     fn run_maybe_operator(&mut self, left: Tracked<Entity>) -> Result<Tracked<Entity>, ParseError> {
@@ -338,19 +494,32 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
             Some(t) => t,
             None => return Ok(left),
         };
+        if let Some(key) = logical_alias(&t.inner) {
+            return self.run_logical(left, key);
+        }
         match &t.inner {
             RawToken::Identifier(ident) => {
                 if ident.inner.is_operator() {
+                    let fixity = self
+                        .fixities
+                        .get(&ident.inner.name)
+                        .copied()
+                        .unwrap_or_default();
+                    if OPERATOR_PRECEDENCE + fixity.precedence < self.min_precedence {
+                        return Ok(left);
+                    }
                     let (ident, pos) = assume!(RawToken::Identifier, self.tokenizer.next());
                     let ident = ident
                         .try_map_anot(|s| Type::try_from(s.as_str()))
                         .map_err(|e| e.into_err(pos))?;
                     // We don't need to run_maybe_operator here because run_operator already does that
-                    self.run_operator(left, Tracked::new(ident).set(pos))
+                    self.run_operator(left, Tracked::new(ident).set(pos), fixity)
                 } else {
                     Err(ParseFault::Unexpected(t.inner.clone()).into_err(t.pos()))
                 }
             }
+            RawToken::Key(Key::And) if !self.in_first => self.run_logical(left, Key::And),
+            RawToken::Key(Key::Or) => self.run_logical(left, Key::Or),
             _ => Ok(left),
         }
     }
@@ -359,18 +528,80 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
         &mut self,
         left: Tracked<Entity>,
         op: Tracked<Anot<Identifier, Type>>,
+        fixity: Fixity,
     ) -> Result<Tracked<Entity>, ParseError> {
-        let right = self.run_chunk()?;
+        // The right side only takes the operators that bind tighter, or the same operators again
+        // if they're right-associative.
+        let precedence = OPERATOR_PRECEDENCE + fixity.precedence;
+        let right_precedence = match fixity.associativity {
+            Associativity::Left => precedence + 1,
+            Associativity::Right => precedence,
+        };
+        let min_precedence = mem::replace(&mut self.min_precedence, right_precedence);
+        let right = self.run_chunk();
+        self.min_precedence = min_precedence;
+        let right = right?;
         assert!(op.inner.inner.is_operator());
         let (op, pos) = op.sep();
         let v = Tracked::new(Entity::Call(Callable::Func(op), vec![left, right])).set(pos);
         self.run_maybe_operator(v)
     }
 
+    // Prefix operators only take the value right after them, so `-x * 2` is `(-x) * 2`.
+    fn run_prefix(
+        &mut self,
+        op: Tracked<Anot<Identifier, Type>>,
+    ) -> Result<Tracked<Entity>, ParseError> {
+        let (op, pos) = op.sep();
+        // `#(+)` passes the operator itself
+        if let Some(RawToken::Key(Key::ParenClose)) = self.tokenizer.peek().map(|t| &t.inner) {
+            return Ok(Tracked::new(Entity::SingleIdent(op)).set(pos));
+        }
+        let min_precedence = mem::replace(&mut self.min_precedence, PREFIX_PRECEDENCE);
+        let operand = self.run_chunk();
+        self.min_precedence = min_precedence;
+        let operand = operand?;
+
+        // Negative literals stay literals so that they can be used in patterns
+        if op.inner.name == "-" {
+            if let Entity::Inlined(v) = &operand.inner {
+                if let Some(v) = negated(v) {
+                    return Ok(Tracked::new(Entity::Inlined(v)).set(pos));
+                }
+            }
+        }
+        Ok(Tracked::new(Entity::Call(Callable::Func(op), vec![operand])).set(pos))
+    }
+
+    // `and`/`or` are left-associative, so the right side only takes what binds tighter
+    fn run_logical(
+        &mut self,
+        left: Tracked<Entity>,
+        key: Key,
+    ) -> Result<Tracked<Entity>, ParseError> {
+        let precedence = match key {
+            Key::And => AND_PRECEDENCE,
+            _ => OR_PRECEDENCE,
+        };
+        if precedence < self.min_precedence {
+            return Ok(left);
+        }
+        let pos = self.tokenizer.next().unwrap().pos();
+        let min_precedence = mem::replace(&mut self.min_precedence, precedence + 1);
+        let right = self.run_chunk();
+        self.min_precedence = min_precedence;
+        let (left, right) = (Box::new(left), Box::new(right?));
+        let v = match key {
+            Key::And => Entity::And(left, right),
+            _ => Entity::Or(left, right),
+        };
+        self.run_maybe_operator(Tracked::new(v).set(pos))
+    }
+
     fn run_if_expression(&mut self) -> Result<Entity, ParseError> {
         let mut branches = Vec::new();
         'outer: loop {
-            let cond = self.run_chunk()?;
+            let cond = self.run_delimited()?;
             '_inner: loop {
                 let (after, pos) = match self.tokenizer.next() {
                     Some(v) => v.sep(),
@@ -382,7 +613,7 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                     _ => return Err(ParseFault::IfWantedThen(after).into_err(pos)),
                 }
             }
-            let eval = self.run_chunk()?;
+            let eval = self.run_delimited()?;
             branches.push((cond, eval));
 
             'inner: loop {
@@ -412,6 +643,7 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
     fn run_first_statement(&mut self) -> Result<Entity, ParseError> {
         let mut branches = Vec::new();
         let mut last = false;
+        let in_first = mem::replace(&mut self.in_first, true);
         'outer: loop {
             let v = self.run_chunk()?;
             branches.push(v);
@@ -430,6 +662,8 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
                     RawToken::Key(Key::And) => continue 'outer,
                     RawToken::NewLine => continue 'inner,
                     RawToken::Key(Key::Then) => {
+                        // The statement after `then` is the value, so `and` may be used again
+                        self.in_first = in_first;
                         last = true;
                         break 'inner;
                     }
@@ -439,6 +673,233 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
         }
     }
 
+    fn run_let(&mut self) -> Result<Entity, ParseError> {
+        let name = match self.tokenizer.next().map(|t| t.sep()) {
+            Some((RawToken::Identifier(ident), _)) if !ident.inner.is_operator() => {
+                ident.inner.name
+            }
+            Some((other, pos)) => {
+                return Err(
+                    ParseFault::GotButExpected(other, vec!["identifier".into()]).into_err(pos)
+                )
+            }
+            None => {
+                return Err(ParseFault::EndedWhileExpecting(vec!["identifier".into()]).into_err(0))
+            }
+        };
+        match self.tokenizer.next().map(|t| t.sep()) {
+            Some((RawToken::Identifier(ident), _)) if ident.inner.name == "=" => {}
+            Some((other, pos)) => {
+                return Err(ParseFault::GotButExpected(other, vec!["=".into()]).into_err(pos))
+            }
+            None => return Err(ParseFault::EndedWhileExpecting(vec!["=".into()]).into_err(0)),
+        }
+        let value = self.run_delimited()?;
+        loop {
+            match self.tokenizer.next().map(|t| t.sep()) {
+                Some((RawToken::NewLine, _)) => continue,
+                Some((RawToken::Key(Key::In), _)) => break,
+                Some((other, pos)) => {
+                    return Err(ParseFault::GotButExpected(other, vec!["in".into()]).into_err(pos))
+                }
+                None => {
+                    return Err(
+                        ParseFault::EndedWhileExpecting(vec!["in".into()]).into_err(value.pos())
+                    )
+                }
+            }
+        }
+        let body = self.run_chunk()?;
+        Ok(Entity::Let(name, Box::new(value), Box::new(body)))
+    }
+
+    fn run_match(&mut self) -> Result<Entity, ParseError> {
+        let value = self.run_delimited()?;
+        let mut arms = Vec::new();
+        loop {
+            match self.tokenizer.peek().map(|t| &t.inner) {
+                Some(RawToken::NewLine) => {
+                    self.tokenizer.next();
+                    continue;
+                }
+                Some(RawToken::Key(Key::Bar)) => {
+                    self.tokenizer.next();
+                }
+                _ => {
+                    if arms.is_empty() {
+                        return Err(ParseFault::MatchMissingArms.into_err(value.pos()));
+                    }
+                    return Ok(Entity::Match(Box::new(value), arms));
+                }
+            }
+
+            let pattern = self.run_pattern()?;
+            match self.tokenizer.next().map(|t| t.sep()) {
+                Some((RawToken::Key(Key::Colon), _)) => {}
+                Some((other, pos)) => {
+                    return Err(ParseFault::GotButExpected(other, vec![":".into()]).into_err(pos))
+                }
+                None => {
+                    return Err(
+                        ParseFault::EndedWhileExpecting(vec![":".into()]).into_err(pattern.pos())
+                    )
+                }
+            }
+            let eval = self.run_chunk()?;
+            arms.push((pattern, eval));
+        }
+    }
+
+    // A pattern is either a single pattern value or an enum variant followed by patterns for its
+    // parameters. Such as `Just [x | xs]`
+    fn run_pattern(&mut self) -> Result<Tracked<Pattern>, ParseError> {
+        if let Some(RawToken::Identifier(ident)) = self.tokenizer.peek().map(|t| &t.inner) {
+            if ident.inner.is_operator() {
+                return self.run_pattern_value();
+            }
+            let (ident, pos) = assume!(RawToken::Identifier, self.tokenizer.next());
+            let ident = ident
+                .try_map_anot(|s| Type::try_from(s.as_str()))
+                .map_err(|e| e.into_err(pos))?;
+            let mut params = Vec::new();
+            while self.next_can_be_pattern() {
+                params.push(self.run_pattern_value()?);
+            }
+            return Ok(Tracked::new(Pattern::Ident(ident, params)).set(pos));
+        }
+        self.run_pattern_value()
+    }
+
+    fn next_can_be_pattern(&mut self) -> bool {
+        match self.tokenizer.peek().map(|t| &t.inner) {
+            Some(RawToken::Identifier(ident)) => !ident.inner.is_operator(),
+            Some(RawToken::Inlined(_))
+            | Some(RawToken::Key(Key::ListOpen))
+            | Some(RawToken::Key(Key::ParenOpen)) => true,
+            _ => false,
+        }
+    }
+
+    fn run_pattern_value(&mut self) -> Result<Tracked<Pattern>, ParseError> {
+        let (t, pos) = match self.tokenizer.next() {
+            Some(t) => t.sep(),
+            None => return Err(ParseFault::EndedWhileExpecting(vec!["pattern".into()]).into_err(0)),
+        };
+        let pattern = match t {
+            RawToken::Inlined(Inlinable::Nothing) => Pattern::Wildcard,
+            RawToken::Inlined(v) => Pattern::Literal(v),
+            RawToken::Identifier(ident) => {
+                if ident.inner.name == "-" {
+                    if let Some(RawToken::Inlined(v)) = self.tokenizer.peek().map(|t| &t.inner) {
+                        if let Some(v) = negated(v) {
+                            self.tokenizer.next();
+                            return Ok(Tracked::new(Pattern::Literal(v)).set(pos));
+                        }
+                    }
+                }
+                if ident.inner.is_operator() {
+                    return Err(ParseFault::GotButExpected(
+                        RawToken::Identifier(ident),
+                        vec!["pattern".into()],
+                    )
+                    .into_err(pos));
+                }
+                let ident = ident
+                    .try_map_anot(|s| Type::try_from(s.as_str()))
+                    .map_err(|e| e.into_err(pos))?;
+                Pattern::Ident(ident, Vec::new())
+            }
+            RawToken::Key(Key::ParenOpen) => return self.run_paren_pattern(pos),
+            RawToken::Key(Key::ListOpen) => self.run_list_pattern(pos)?,
+            other => {
+                return Err(ParseFault::GotButExpected(other, vec!["pattern".into()]).into_err(pos))
+            }
+        };
+        Ok(Tracked::new(pattern).set(pos))
+    }
+
+    // Either a parenthesized pattern such as `(Just x)` or a tuple such as `(a, b)`
+    pub fn run_paren_pattern(&mut self, open_pos: usize) -> Result<Tracked<Pattern>, ParseError> {
+        let first = self.run_pattern()?;
+        let mut entries = Vec::new();
+        loop {
+            match self.tokenizer.next().map(|t| t.sep()) {
+                Some((RawToken::Key(Key::ParenClose), _)) if entries.is_empty() => {
+                    return Ok(first)
+                }
+                Some((RawToken::Key(Key::ParenClose), _)) => {
+                    entries.insert(0, first);
+                    return Ok(Tracked::new(Pattern::Tuple(entries)).set(open_pos));
+                }
+                Some((RawToken::Key(Key::Comma), _)) => entries.push(self.run_pattern()?),
+                Some((other, pos)) => {
+                    return Err(
+                        ParseFault::GotButExpected(other, vec![",".into(), ")".into()])
+                            .into_err(pos),
+                    )
+                }
+                None => return Err(ParseFault::Unmatched(Key::ParenOpen).into_err(open_pos)),
+            }
+        }
+    }
+
+    // [], [a, b] or [x | xs]
+    fn run_list_pattern(&mut self, open_pos: usize) -> Result<Pattern, ParseError> {
+        let mut entries = Vec::new();
+        if let Some(RawToken::Key(Key::ListClose)) = self.tokenizer.peek().map(|t| &t.inner) {
+            self.tokenizer.next();
+            return Ok(Pattern::List(entries, None));
+        }
+        loop {
+            entries.push(self.run_pattern()?);
+            match self.tokenizer.next().map(|t| t.sep()) {
+                Some((RawToken::Key(Key::Comma), _)) => continue,
+                Some((RawToken::Key(Key::ListClose), _)) => {
+                    return Ok(Pattern::List(entries, None))
+                }
+                Some((RawToken::Key(Key::Bar), _)) => {
+                    let rest = self.run_pattern_value()?;
+                    return match self.tokenizer.next().map(|t| t.sep()) {
+                        Some((RawToken::Key(Key::ListClose), _)) => {
+                            Ok(Pattern::List(entries, Some(Box::new(rest))))
+                        }
+                        Some((other, pos)) => {
+                            Err(ParseFault::GotButExpected(other, vec!["]".into()]).into_err(pos))
+                        }
+                        None => Err(ParseFault::Unmatched(Key::ListOpen).into_err(open_pos)),
+                    };
+                }
+                Some((other, pos)) => {
+                    return Err(ParseFault::GotButExpected(
+                        other,
+                        vec![",".into(), "|".into(), "]".into()],
+                    )
+                    .into_err(pos))
+                }
+                None => return Err(ParseFault::Unmatched(Key::ListOpen).into_err(open_pos)),
+            }
+        }
+    }
+
+    // `(a, b)`, the first entry and its comma have already been taken
+    fn run_tuple(&mut self, first: Tracked<Entity>, open_pos: usize) -> Result<Entity, ParseError> {
+        let mut entries = vec![first];
+        loop {
+            entries.push(self.run_delimited()?);
+            match self.tokenizer.next().map(|t| t.sep()) {
+                Some((RawToken::Key(Key::ParenClose), _)) => return Ok(Entity::Tuple(entries)),
+                Some((RawToken::Key(Key::Comma), _)) => continue,
+                Some((other, pos)) => {
+                    return Err(
+                        ParseFault::GotButExpected(other, vec![",".into(), ")".into()])
+                            .into_err(pos),
+                    )
+                }
+                None => return Err(ParseFault::Unmatched(Key::ParenOpen).into_err(open_pos)),
+            }
+        }
+    }
+
     // forever loop while `next() == ,` then on `== ]` return. On other then error
     fn run_list(&mut self) -> Result<Entity, ParseError> {
         let mut buf = Vec::new();
@@ -452,7 +913,7 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
         }
 
         loop {
-            let v = self.run_chunk()?;
+            let v = self.run_delimited()?;
             buf.push(v);
             match self.tokenizer.next().map(|t| t.sep()) {
                 Some((RawToken::Key(Key::ListClose), _)) => {
@@ -489,10 +950,16 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
             }
         };
 
-        match self.tokenizer.next().map(|t| t.sep()) {
-            Some((RawToken::Key(Key::Dot), _)) => {}
-            Some((other, pos)) => {
-                return Err(ParseFault::GotButExpected(other, vec![".".into()]).into_err(pos))
+        match self.tokenizer.peek().map(|t| &t.inner) {
+            Some(RawToken::Key(Key::Dot)) => {
+                self.tokenizer.next();
+            }
+            Some(_) => {
+                // `{ y 3 } << record` modifies the record coming from the pipe
+                let fields = self.run_record_fields_from(name.inner.name, pos)?;
+                let record = self.run_record_pipe()?;
+                let entity = Entity::RecordUpdate(Box::new(record), fields);
+                return Ok(Tracked::new(entity).set(pos));
             }
             None => return Err(ParseFault::EndedWhileExpecting(vec![".".into()]).into_err(pos)),
         }
@@ -520,7 +987,19 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
             }
         };
 
-        let value = self.run_chunk()?;
+        self.run_record_fields_from(name, pos)
+    }
+
+    fn run_record_fields_from(
+        &mut self,
+        name: String,
+        pos: usize,
+    ) -> Result<Vec<(String, Tracked<Entity>)>, ParseError> {
+        // `{ p . x << 1 + 1 }` is the same as `{ p . x (1 + 1) }`
+        if let Some(RawToken::Key(Key::Pipe)) = self.tokenizer.peek().map(|t| &t.inner) {
+            self.tokenizer.next();
+        }
+        let value = self.run_delimited()?;
 
         let (after, _pos) = match self.tokenizer.next() {
             None => return Err(ParseFault::EndedWhileExpecting(vec!["}".into()]).into_err(pos)),
@@ -536,4 +1015,37 @@ impl<I: Iterator<Item = char>> AstBuilder<'_, I> {
             _ => panic!("ET: Unexpected {:?}", after),
         }
     }
+
+    fn run_record_pipe(&mut self) -> Result<Tracked<Entity>, ParseError> {
+        match self.tokenizer.next().map(|t| t.sep()) {
+            Some((RawToken::Key(Key::Pipe), _)) => self.run_chunk(),
+            Some((RawToken::NewLine, _)) => self.run_record_pipe(),
+            Some((other, pos)) => {
+                Err(ParseFault::GotButExpected(other, vec!["<<".into()]).into_err(pos))
+            }
+            None => Err(ParseFault::EndedWhileExpecting(vec!["<<".into()]).into_err(0)),
+        }
+    }
+}
+
+fn negated(v: &Inlinable) -> Option<Inlinable> {
+    match v {
+        Inlinable::Int(n) => Some(Inlinable::Int(-n)),
+        Inlinable::Float(n) => Some(Inlinable::Float(-n)),
+        _ => None,
+    }
+}
+
+// `&&` and `||` are kept as aliases of `and`/`or`, so they short-circuit in the same way
+fn logical_alias(token: &RawToken) -> Option<Key> {
+    match token {
+        RawToken::Identifier(ident) if ident.inner.path.is_empty() => {
+            match ident.inner.name.as_str() {
+                "&&" => Some(Key::And),
+                "||" => Some(Key::Or),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
 }
@@ -1,10 +1,12 @@
 use super::IrBuilder;
-use crate::ir;
+use crate::ir::{self, bridge::Bridged};
 use crate::parser::{
-    ast, r#type::CustomType, Anot, Identifier, MaybeType, ParseError, ParseFault, Tracked, Type,
+    ast, r#type::CustomType, Anot, FileSource, Identifier, Key, MaybeType, ParseError, ParseFault,
+    Tracked, Type,
 };
 
-use super::{Identifiable, Meta};
+use super::{Capture, IdentMeta, Identifiable, Meta};
+use std::collections::HashMap;
 
 impl<'a> IrBuilder {
     pub fn find_and_build_function(
@@ -70,9 +72,63 @@ impl<'a> IrBuilder {
                     .into_err(0)
                 })?,
         };
+        if !self.parser.is_visible(
+            self_fid,
+            fid,
+            self.parser.modules[fid].functions[**funcid].public,
+        ) {
+            return Err(ParseFault::NotExported(ident.clone()).into_err(0));
+        }
         Ok((fid, **funcid))
     }
 
+    // Defaults are checked once where the type is declared, even if no record ever uses them.
+    // They can't see anything from the scope of the records they end up in.
+    pub fn check_defaults(&self) -> Result<(), ParseError> {
+        for (fid, module) in self.parser.modules.iter().enumerate() {
+            for (tid, custom) in module.types.iter().enumerate() {
+                let r#struct = match custom {
+                    CustomType::Struct(r#struct) => r#struct,
+                    CustomType::Enum(_) => continue,
+                };
+                for (i, default) in r#struct.defaults.iter().enumerate() {
+                    let entity = match default {
+                        Some(entity) => entity,
+                        None => continue,
+                    };
+                    let (name, field_t) = &r#struct.fields[i];
+                    let expected_t = self
+                        .parser
+                        .destruct_custom_type(fid, field_t.clone())
+                        .map_err(|e| e.into_err(entity.pos()).fallback_fid(fid))?;
+                    let mut default_meta = Meta {
+                        fid,
+                        return_type: expected_t.clone(),
+                        ..Meta::default()
+                    };
+                    let (t, ir) = self
+                        .build(entity, &mut default_meta)
+                        .map_err(|e| e.fallback_index(entity.pos()).fallback_fid(fid))?;
+                    let t = t.unwrap();
+                    if !self
+                        .parser
+                        .generic_cmp(&mut HashMap::new(), &t, &expected_t)
+                    {
+                        return Err(ParseFault::FieldTypeMismatch(Box::new((
+                            name.clone(),
+                            expected_t,
+                            t,
+                        )))
+                        .into_err(entity.pos())
+                        .fallback_fid(fid));
+                    }
+                    self.defaults.borrow_mut().insert((fid, tid, i), (t, ir));
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn build_function(
         &'a self,
         mut meta: Meta,
@@ -80,24 +136,125 @@ impl<'a> IrBuilder {
     ) -> Result<(Type, usize), ParseError> {
         let findex = self.gen_id(&meta);
 
-        let (t, ir) = self
-            .build(entry, &mut meta)
-            .map_err(|e| e.fallback_fid(meta.fid))?;
+        let (t, ir) = self.build(entry, &mut meta).map_err(|mut e| {
+            // The prelude has no source file to point into, so faults from within it are
+            // instead reported at wherever the prelude function was called from.
+            if self.parser.modules[meta.fid].module_path == FileSource::Prelude {
+                if e.module_fid.is_none() || e.module_fid == Some(meta.fid) {
+                    e.source_index = 0;
+                    e.module_fid = None;
+                }
+                e
+            } else {
+                e.fallback_fid(meta.fid)
+            }
+        })?;
         let t = t.unwrap();
 
         let expected = self
             .parser
-            .destruct_custom_type(meta.fid, meta.return_type.clone());
-        if expected != Type::Nothing && t != expected {
-            meta.return_type = expected;
+            .destruct_custom_type(meta.fid, meta.return_type.clone())
+            .map_err(|e| e.into_err(entry.pos()).fallback_fid(meta.fid))?;
+        if expected != Type::Nothing && !t.fits(&expected) {
             return Err(ParseFault::FnTypeReturnMismatch(Box::new(meta), t).into_err(entry.pos()));
         }
         println!("{} fi{} -> {}", &meta.ident, findex, &ir);
 
         self.complete(findex, ir);
+        self.record_origin(findex, &meta, entry.pos(), false);
         Ok((t, findex))
     }
 
+    // Where-bindings are built in the scope of their function so that bindings from lambdas and
+    // match arms can't shadow what they refer to. Inside of a lambda the value is instead built by
+    // the frame creating the lambda and captured.
+    fn where_binding(
+        &'a self,
+        name: &str,
+        pos: usize,
+        meta: &mut Meta,
+    ) -> Result<(MaybeType, ir::Entity), ParseError> {
+        let i = meta.identifiers[..meta.scope]
+            .iter()
+            .rposition(|(n, _)| n == name)
+            .expect("where binding missing from scope");
+        let (fid, funcid, whereid) = match meta.identifiers[i].1.ident {
+            Identifiable::Captured(id) => {
+                let im = &mut meta.identifiers[i].1;
+                im.use_counter += 1;
+                return Ok((im.r#type.clone(), ir::Entity::Captured(id as u16)));
+            }
+            Identifiable::Where((fid, funcid), whereid) => (fid, funcid, whereid),
+            Identifiable::Param(_) | Identifiable::Capturable => unreachable!(),
+        };
+        match &meta.outer {
+            None => {
+                let func = &self.parser.modules[fid].functions[funcid];
+                // `let` and `match` bindings are hidden from the where binding, but they still
+                // occupy their parameter slots at runtime.
+                let inner = meta.identifiers.split_off(meta.scope);
+                let hidden = inner
+                    .iter()
+                    .filter(|(_, im)| matches!(im.ident, Identifiable::Param(_)))
+                    .count();
+                meta.hidden_params += hidden;
+                let built = self.build(&func.wheres[whereid].1.clone().set(pos), meta);
+                meta.hidden_params -= hidden;
+                meta.identifiers.extend(inner);
+                if func.is_destructured_where(whereid) {
+                    let (t, v) = built?;
+                    if let Some(pattern) = func.destructured_pattern(whereid) {
+                        let value_t = t.clone().unwrap();
+                        if self.refutable(&pattern.inner, &value_t) {
+                            return Err(ParseFault::WherePatternRefutable(Box::new((
+                                pattern.inner.clone(),
+                                value_t,
+                            )))
+                            .into_err(pattern.pos())
+                            .fallback_fid(fid));
+                        }
+                    }
+                    return Ok((t, ir::Entity::Memoized(whereid as u16, Box::new(v))));
+                }
+                built
+            }
+            Some(outer) => {
+                // The snapshot is only used to know the type, the value is built once the lambda
+                // is complete.
+                let (t, _) = self.where_binding(name, pos, &mut (**outer).clone())?;
+                let id = meta.captured.len();
+                meta.captured.push(Capture::Where(name.to_string()));
+                meta.identifiers[i].1 = IdentMeta {
+                    use_counter: 1,
+                    r#type: t.clone(),
+                    ident: Identifiable::Captured(id),
+                };
+                Ok((t, ir::Entity::Captured(id as u16)))
+            }
+        }
+    }
+
+    // Gathers the values a lambda built with `lambda_meta` needs from the frame it's created in
+    fn capture(
+        &'a self,
+        lambda_meta: &Meta,
+        pos: usize,
+        meta: &mut Meta,
+    ) -> Result<Vec<ir::Capturable>, ParseError> {
+        let mut to_capture = Vec::with_capacity(lambda_meta.captured.len());
+        for capture in lambda_meta.captured.iter() {
+            let capturable = match capture {
+                Capture::Identifier(i) => meta.capturable(*i),
+                Capture::Where(name) => {
+                    let (_, v) = self.where_binding(name, pos, meta)?;
+                    ir::Capturable::ParentWhere(v)
+                }
+            };
+            to_capture.push(capturable);
+        }
+        Ok(to_capture)
+    }
+
     fn build(
         &'a self,
         token: &'a Tracked<ast::Entity>,
@@ -150,6 +307,8 @@ impl<'a> IrBuilder {
                                         panic!("ET: This `where` identifier cannot be used as a function");
                                     }
                                 }
+                                // Captured by `try_use`
+                                Identifiable::Capturable => unreachable!(),
                             };
                             let (_takes, gives) =
                                 destruct_callable_ident(identmeta.r#type.clone(), param_types)
@@ -157,6 +316,15 @@ impl<'a> IrBuilder {
                             Ok((MaybeType::Known(gives), ir))
                         }
                         None => {
+                            if let Some(variant) = self
+                                .parser
+                                .find_variant(meta.fid, ident)
+                                .map_err(|e| e.into_err(token.pos()).fallback_fid(meta.fid))?
+                            {
+                                return self
+                                    .construct_enum(variant, param_types, evaluated_params)
+                                    .map_err(|e| e.into_err(token.pos()).fallback_fid(meta.fid));
+                            }
                             let (t, findex) = self
                                 .find_and_build_function(meta.fid, ident, &mut param_types)
                                 .map_err(|e| {
@@ -171,6 +339,42 @@ impl<'a> IrBuilder {
                     ast::Callable::Builtin(ident) => {
                         let (id, nt) = ir::bridge::get_funcid(&ident.inner.name)
                             .map_err(|e| e.into_err(token.pos()).fallback_fid(meta.fid))?;
+                        if let Bridged::eq | Bridged::lt | Bridged::le = id {
+                            let first = param_types[0].clone().unwrap();
+                            for t in param_types.iter() {
+                                let t = t.clone().unwrap();
+                                if t != first {
+                                    return Err(ParseFault::ComparingDifferentTypes(Box::new((
+                                        first, t,
+                                    )))
+                                    .into_err(token.pos())
+                                    .fallback_fid(meta.fid));
+                                }
+                                self.parser
+                                    .comparable(&t, &t, &mut Vec::new())
+                                    .map_err(|e| e.into_err(token.pos()).fallback_fid(meta.fid))?;
+                            }
+                        }
+                        // The arithmetic builtins are generic but can only calculate with numbers.
+                        // Values that couldn't be inferred are given the benefit of the doubt.
+                        if let Bridged::add
+                        | Bridged::sub
+                        | Bridged::mul
+                        | Bridged::div
+                        | Bridged::rem = id
+                        {
+                            match param_types[0].clone().unwrap() {
+                                Type::Int | Type::Float | Type::Generic(_) => {}
+                                t => {
+                                    return Err(ParseFault::BuiltinTypeMismatch(
+                                        id.as_ref().to_string(),
+                                        t,
+                                    )
+                                    .into_err(token.pos())
+                                    .fallback_fid(meta.fid))
+                                }
+                            }
+                        }
                         let mt = match nt {
                             ir::bridge::NaiveType::Known(t) => MaybeType::Known(t),
                             ir::bridge::NaiveType::Matching(i) => param_types[i as usize].clone(),
@@ -200,7 +404,7 @@ impl<'a> IrBuilder {
                         // The lambda entity expects the first parameter to be the actual body
                         // itself instead.
                         evaluated_params.insert(0, v);
-                        let to_capture = meta.was_used(&new_meta);
+                        let to_capture = self.capture(&new_meta, token.pos(), meta)?;
                         Ok((t, ir::Entity::Lambda(evaluated_params, to_capture)))
                     }
                 }
@@ -238,7 +442,7 @@ impl<'a> IrBuilder {
                             .collect::<Vec<_>>();
                         new_meta.lambda_swap(param_names, infered_param_types.as_slice());
                         let (t, v) = self.build(lambda_token, &mut new_meta)?;
-                        let to_capture = meta.was_used(&new_meta);
+                        let to_capture = self.capture(&new_meta, token.pos(), meta)?;
                         Ok((
                             MaybeType::Known(Type::Function(Box::new((
                                 infered_param_types
@@ -247,15 +451,22 @@ impl<'a> IrBuilder {
                                     .collect::<Vec<_>>(),
                                 t.unwrap(),
                             )))),
-                            ir::Entity::LambdaPointer(Box::new((v, to_capture))),
+                            ir::Entity::LambdaPointer(
+                                self.complete_closure(v, meta, token.pos()),
+                                to_capture,
+                            ),
                         ))
                     }
                     ast::Passable::Value(inlinable) => Ok((
                         (MaybeType::Known(Type::Function(Box::new((vec![], inlinable.into()))))),
-                        ir::Entity::LambdaPointer(Box::new((
-                            ir::Entity::Inlined(inlinable.clone().into()),
+                        ir::Entity::LambdaPointer(
+                            self.complete_closure(
+                                ir::Entity::Inlined(inlinable.clone().into()),
+                                meta,
+                                token.pos(),
+                            ),
                             vec![],
-                        ))),
+                        ),
                     )),
                 }
             }
@@ -263,10 +474,44 @@ impl<'a> IrBuilder {
                 .if_expression(branches, else_do, meta)
                 .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
             ast::Entity::First(branches) => self.first_statement(branches, meta),
+            ast::Entity::Match(value, arms) => self
+                .match_expression(value, arms, meta)
+                .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
             ast::Entity::Record(ident, fields) => self
                 .record(ident, fields, meta)
-                .map_err(|e| e.fallback_fid(token.pos())),
+                .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
+            ast::Entity::RecordUpdate(record, fields) => self
+                .record_update(record, fields, meta)
+                .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
+            ast::Entity::FieldAccess(record, field) => self
+                .field_access(record, field, meta)
+                .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
+            ast::Entity::Exit(code) => {
+                let (t, v) = self.build(code, meta)?;
+                match t.unwrap() {
+                    Type::Int => Ok((
+                        MaybeType::Known(Type::Never),
+                        ir::Entity::Exit(Box::new(v)),
+                    )),
+                    t => Err(ParseFault::ExitCodeNotInt(t)
+                        .into_err(code.pos())
+                        .fallback_fid(meta.fid)),
+                }
+            }
+            ast::Entity::Let(name, value, body) => self
+                .let_expression(name, value, body, meta)
+                .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
+            ast::Entity::And(left, right) => self
+                .logical(Key::And, left, right, meta)
+                .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
+            ast::Entity::Or(left, right) => self
+                .logical(Key::Or, left, right, meta)
+                .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
+            ast::Entity::Try(inner) => self
+                .try_expression(inner, meta)
+                .map_err(|e| e.fallback_index(token.pos()).fallback_fid(meta.fid)),
             ast::Entity::List(branches) => self.list(branches, meta),
+            ast::Entity::Tuple(entries) => self.tuple(entries, meta),
             ast::Entity::SingleIdent(ident) => match meta.try_use(&ident.inner.name) {
                 Some(found) => match found.ident {
                     Identifiable::Param(id) => {
@@ -291,12 +536,21 @@ impl<'a> IrBuilder {
                     Identifiable::Captured(id) => {
                         Ok((found.r#type.clone(), ir::Entity::Captured(id as u16)))
                     }
-                    Identifiable::Where((fid, funcid), whereid) => {
-                        let func = &self.parser.modules[fid].functions[funcid];
-                        self.build(&func.wheres[whereid].1.clone().set(token.pos()), meta)
+                    Identifiable::Where(..) => {
+                        self.where_binding(&ident.inner.name, token.pos(), meta)
                     }
+                    Identifiable::Capturable => unreachable!(),
                 },
                 None => {
+                    if let Some(variant) = self
+                        .parser
+                        .find_variant(meta.fid, ident)
+                        .map_err(|e| e.into_err(token.pos()).fallback_fid(meta.fid))?
+                    {
+                        return self
+                            .construct_enum(variant, Vec::new(), Vec::new())
+                            .map_err(|e| e.into_err(token.pos()).fallback_fid(meta.fid));
+                    }
                     // Lets see if it's a constant
                     const NO_PARAMS: &[MaybeType] = &[];
                     let (entry, meta) = self
@@ -336,13 +590,15 @@ impl<'a> IrBuilder {
         fields: &[(String, Tracked<ast::Entity>)],
         meta: &mut Meta,
     ) -> Result<(MaybeType, ir::Entity), ParseError> {
-        match meta.try_use(&ident.inner.name) {
-            Some(local) => {
+        match meta.identifier(&ident.inner.name) {
+            Some(_local) => {
                 // We're modifying a struct in the current scope
-                unimplemented!();
+                let record = Tracked::new(ast::Entity::SingleIdent(ident.clone()));
+                self.record_update(&record, fields, meta)
             }
             None => {
                 // We're constructing a new struct
+                check_duplicate_fields(fields)?;
                 let (fid, tid) = self
                     .parser
                     .find_type(meta.fid, &ident)
@@ -353,17 +609,52 @@ impl<'a> IrBuilder {
                 // in the new one.
                 match &self.parser.modules[fid].types[tid] {
                     CustomType::Struct(r#struct) => {
-                        let sorted = r#struct.copy_order_for(fields);
+                        let sorted = r#struct.copy_order_for(fields).map_err(|e| e.into_err(0))?;
+                        // Type arguments are either annotated `{ box<int> . value 1 }` or inferred
+                        // from the fields
+                        let annotated = ident
+                            .anot
+                            .iter()
+                            .map(|t| self.parser.destruct_custom_type(meta.fid, t.clone()))
+                            .collect::<Result<Vec<Type>, ParseFault>>()
+                            .map_err(|e| e.into_err(0))?;
+                        let mut generics =
+                            self.parser.modules[fid].types[tid].generics_for(&annotated);
                         let mut evaluated_fields = Vec::with_capacity(sorted.len());
-                        for (entity, expected_t) in sorted.iter() {
-                            let (t, ir) = self.build(entity, meta)?;
-                            if t.unwrap() != *expected_t {
-                                panic!("ET");
+                        for (i, (entity, is_default, expected_t)) in sorted.iter().enumerate() {
+                            let expected_t = self
+                                .parser
+                                .destruct_custom_type(fid, expected_t.clone())
+                                .map_err(|e| e.into_err(entity.pos()))?
+                                .decoded(&generics);
+                            let (t, ir) = if *is_default {
+                                self.defaults.borrow()[&(fid, tid, i)].clone()
+                            } else {
+                                let (t, ir) = self.build(entity, meta)?;
+                                (t.unwrap(), ir)
+                            };
+                            if !self.parser.generic_cmp(&mut generics, &t, &expected_t) {
+                                let err = ParseFault::FieldTypeMismatch(Box::new((
+                                    r#struct.fields[i].0.clone(),
+                                    expected_t,
+                                    t,
+                                )))
+                                .into_err(entity.pos());
+                                return Err(if *is_default {
+                                    err.fallback_fid(fid)
+                                } else {
+                                    err
+                                });
                             }
                             evaluated_fields.push(ir);
                         }
                         let ir = ir::Entity::ConstructRecord(evaluated_fields);
-                        let t = MaybeType::Known(Type::KnownCustom(fid, tid));
+                        let type_args = r#struct
+                            .type_args
+                            .iter()
+                            .map(|t| t.clone().decoded(&generics))
+                            .collect();
+                        let t = MaybeType::Known(Type::KnownCustom(fid, tid, type_args));
                         Ok((t, ir))
                     }
                     CustomType::Enum(r#enum) => {
@@ -374,6 +665,299 @@ impl<'a> IrBuilder {
         }
     }
 
+    // Copies an existing record and replaces the given fields
+    fn record_update(
+        &'a self,
+        record: &Tracked<ast::Entity>,
+        fields: &[(String, Tracked<ast::Entity>)],
+        meta: &mut Meta,
+    ) -> Result<(MaybeType, ir::Entity), ParseError> {
+        check_duplicate_fields(fields)?;
+        let (t, v) = self.build(record, meta)?;
+        let t = t.unwrap();
+        let (fid, tid, generics) = match &t {
+            Type::KnownCustom(fid, tid, args) => (
+                *fid,
+                *tid,
+                self.parser.modules[*fid].types[*tid].generics_for(args),
+            ),
+            other => {
+                return Err(ParseFault::FieldAccessOnNonRecord(other.clone()).into_err(record.pos()))
+            }
+        };
+        let r#struct = match &self.parser.modules[fid].types[tid] {
+            CustomType::Struct(r#struct) => r#struct,
+            CustomType::Enum(_) => {
+                return Err(ParseFault::FieldAccessOnNonRecord(t).into_err(record.pos()));
+            }
+        };
+        let mut replacements = Vec::with_capacity(fields.len());
+        for (name, entity) in fields.iter() {
+            let i = r#struct
+                .fields
+                .iter()
+                .position(|(n, _)| n == name)
+                .ok_or_else(|| {
+                    ParseFault::FieldNotFound(
+                        name.clone(),
+                        r#struct.fields.iter().map(|(n, _)| n.clone()).collect(),
+                    )
+                    .into_err(entity.pos())
+                })?;
+            let expected_t = self
+                .parser
+                .destruct_custom_type(fid, r#struct.fields[i].1.clone())
+                .map_err(|e| e.into_err(entity.pos()))?
+                .decoded(&generics);
+            let (field_t, field_v) = self.build(entity, meta)?;
+            let field_t = field_t.unwrap();
+            if !field_t.fits(&expected_t) {
+                return Err(ParseFault::FieldTypeMismatch(Box::new((
+                    name.clone(),
+                    expected_t,
+                    field_t,
+                )))
+                .into_err(entity.pos()));
+            }
+            replacements.push((i as u16, field_v));
+        }
+        Ok((
+            MaybeType::Known(t),
+            ir::Entity::UpdateRecord(Box::new(v), replacements),
+        ))
+    }
+
+    fn field_access(
+        &'a self,
+        record: &'a Tracked<ast::Entity>,
+        field: &str,
+        meta: &mut Meta,
+    ) -> Result<(MaybeType, ir::Entity), ParseError> {
+        let (t, v) = self.build(record, meta)?;
+        let t = t.unwrap();
+        let (fid, tid, generics) = match &t {
+            Type::KnownCustom(fid, tid, args) => (
+                *fid,
+                *tid,
+                self.parser.modules[*fid].types[*tid].generics_for(args),
+            ),
+            other => return Err(ParseFault::FieldAccessOnNonRecord(other.clone()).into_err(0)),
+        };
+        let r#struct = match &self.parser.modules[fid].types[tid] {
+            CustomType::Struct(r#struct) => r#struct,
+            CustomType::Enum(_) => {
+                return Err(ParseFault::FieldAccessOnNonRecord(t).into_err(0));
+            }
+        };
+        match r#struct.fields.iter().position(|(name, _)| name == field) {
+            Some(i) => {
+                let field_t = self
+                    .parser
+                    .destruct_custom_type(fid, r#struct.fields[i].1.clone())
+                    .map_err(|e| e.into_err(0))?
+                    .decoded(&generics);
+                Ok((
+                    MaybeType::Known(field_t),
+                    ir::Entity::FieldAccess(Box::new(v), i as u16),
+                ))
+            }
+            None => Err(ParseFault::FieldNotFound(
+                field.to_string(),
+                r#struct
+                    .fields
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+            )
+            .into_err(0)),
+        }
+    }
+
+    // The bound value is appended to the parameters of the current scope at runtime, the same way
+    // as the bindings of match arms.
+    fn let_expression(
+        &'a self,
+        name: &str,
+        value: &'a Tracked<ast::Entity>,
+        body: &'a Tracked<ast::Entity>,
+        meta: &mut Meta,
+    ) -> Result<(MaybeType, ir::Entity), ParseError> {
+        let (t, v) = self.build(value, meta)?;
+        let scope = meta.identifiers.len();
+        meta.identifiers.push((
+            name.to_string(),
+            IdentMeta {
+                use_counter: 0,
+                r#type: t,
+                ident: Identifiable::Param(meta.next_param()),
+            },
+        ));
+        let (t, body_v) = self.build(body, meta)?;
+        meta.identifiers.truncate(scope);
+        Ok((t, ir::Entity::Let(Box::new((v, body_v)))))
+    }
+
+    fn logical(
+        &'a self,
+        key: Key,
+        left: &'a Tracked<ast::Entity>,
+        right: &'a Tracked<ast::Entity>,
+        meta: &mut Meta,
+    ) -> Result<(MaybeType, ir::Entity), ParseError> {
+        let mut sides = Vec::with_capacity(2);
+        for &side in [left, right].iter() {
+            let (t, v) = self.build(side, meta)?;
+            let t = t.unwrap();
+            if t != Type::Bool {
+                return Err(ParseFault::LogicalNotBoolean(key, t).into_err(side.pos()));
+            }
+            sides.push(v);
+        }
+        let right = sides.pop().unwrap();
+        let left = sides.pop().unwrap();
+        let v = match key {
+            Key::And => ir::Entity::And(Box::new((left, right))),
+            _ => ir::Entity::Or(Box::new((left, right))),
+        };
+        Ok((MaybeType::Known(Type::Bool), v))
+    }
+
+    // `try` unwraps `Just`/`Ok` and otherwise returns the `None`/`Err` as-is from the function, so the
+    // function needs to return the same kind of type. For `result` the error types need to agree.
+    fn try_expression(
+        &'a self,
+        inner: &'a Tracked<ast::Entity>,
+        meta: &mut Meta,
+    ) -> Result<(MaybeType, ir::Entity), ParseError> {
+        if meta.outer.is_some() {
+            return Err(ParseFault::TryInLambda.into_err(0));
+        }
+        let (t, v) = self.build(inner, meta)?;
+        let t = t.unwrap();
+        // The variant that's unwrapped is looked up by name rather than assuming its position
+        let (fid, tid, args, success) = match &t {
+            Type::KnownCustom(fid, tid, args)
+                if self.parser.is_prelude_type(*fid, *tid, "maybe") =>
+            {
+                (*fid, *tid, args, "Just")
+            }
+            Type::KnownCustom(fid, tid, args)
+                if self.parser.is_prelude_type(*fid, *tid, "result") =>
+            {
+                (*fid, *tid, args, "Ok")
+            }
+            _ => return Err(ParseFault::TryOnNonFallible(t).into_err(inner.pos())),
+        };
+        let custom = &self.parser.modules[fid].types[tid];
+        let r#enum = match custom {
+            CustomType::Enum(r#enum) => r#enum,
+            CustomType::Struct(_) => unreachable!(),
+        };
+        let payload_types = |args: &[Type], payload: &[Type]| {
+            let generics = custom.generics_for(args);
+            payload
+                .iter()
+                .map(|t| {
+                    self.parser
+                        .destruct_custom_type(fid, t.clone())
+                        .map(|t| t.decoded(&generics))
+                })
+                .collect::<Result<Vec<Type>, ParseFault>>()
+        };
+        let (tag, unwrapped) = match r#enum.get_variant(success) {
+            Some((tag, payload)) if payload.len() == 1 => {
+                let mut payload = payload_types(args, payload).map_err(|e| e.into_err(0))?;
+                (tag, payload.remove(0))
+            }
+            _ => {
+                return Err(
+                    ParseFault::VariantNotFound(success.into(), t.clone()).into_err(inner.pos())
+                )
+            }
+        };
+
+        // Every other variant is returned as-is, so the function needs to return the same type
+        // with the same payloads for them
+        let returns = self
+            .parser
+            .destruct_custom_type(meta.fid, meta.return_type.clone())
+            .map_err(|e| e.into_err(0))?;
+        let compatible = match &returns {
+            Type::KnownCustom(rfid, rtid, rargs) => {
+                fid == *rfid
+                    && tid == *rtid
+                    && r#enum
+                        .fields
+                        .iter()
+                        .filter(|(name, _)| name != success)
+                        .all(|(_, payload)| {
+                            match (payload_types(args, payload), payload_types(rargs, payload)) {
+                                (Ok(got), Ok(wanted)) => got
+                                    .iter()
+                                    .zip(wanted.iter())
+                                    .all(|(got, wanted)| got.fits(wanted)),
+                                _ => false,
+                            }
+                        })
+            }
+            _ => false,
+        };
+        if !compatible {
+            return Err(ParseFault::TryReturnMismatch(Box::new((t, returns))).into_err(0));
+        }
+        Ok((
+            MaybeType::Known(unwrapped),
+            ir::Entity::Try(Box::new(v), tag),
+        ))
+    }
+
+    fn construct_enum(
+        &'a self,
+        (fid, tid, tag): (usize, usize, u16),
+        param_types: Vec<MaybeType>,
+        evaluated_params: Vec<ir::Entity>,
+    ) -> Result<(MaybeType, ir::Entity), ParseFault> {
+        let r#enum = match &self.parser.modules[fid].types[tid] {
+            CustomType::Enum(r#enum) => r#enum,
+            CustomType::Struct(_) => unreachable!(),
+        };
+        let (name, payload) = &r#enum.fields[tag as usize];
+        let takes = payload
+            .iter()
+            .map(|t| self.parser.destruct_custom_type(fid, t.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if takes.len() != param_types.len() {
+            return Err(ParseFault::VariantParamAmountMismatch(
+                name.clone(),
+                takes.len(),
+                param_types.len(),
+            ));
+        }
+        // Type arguments that aren't given by the payload (such as for `None`) are left as generics
+        let mut generics = HashMap::new();
+        for (want, got) in takes.iter().zip(param_types.iter()) {
+            if !self
+                .parser
+                .generic_cmp(&mut generics, &got.clone().unwrap(), want)
+            {
+                return Err(ParseFault::VariantParamMismatch(Box::new((
+                    name.clone(),
+                    takes,
+                    param_types,
+                ))));
+            }
+        }
+        let type_args = r#enum
+            .type_args
+            .iter()
+            .map(|t| t.clone().decoded(&generics))
+            .collect();
+        Ok((
+            MaybeType::Known(Type::KnownCustom(fid, tid, type_args)),
+            ir::Entity::ConstructEnum(tag, name.as_str().into(), evaluated_params),
+        ))
+    }
+
     fn list(
         &'a self,
         branches: &'a [Tracked<ast::Entity>],
@@ -386,13 +970,14 @@ impl<'a> IrBuilder {
             let t = t.unwrap();
             match &expected_t {
                 None => expected_t = Some(t),
-                Some(expected) => {
-                    if t != *expected {
+                Some(expected) => match t.merge(expected) {
+                    Some(merged) => expected_t = Some(merged),
+                    None => {
                         return Err(ParseFault::ListEntryTypeMismatch(t, expected.clone(), i)
                             .into_err(branch.pos())
-                            .fallback_fid(meta.fid));
+                            .fallback_fid(meta.fid))
                     }
-                }
+                },
             }
             buf.push(v);
         }
@@ -406,6 +991,24 @@ impl<'a> IrBuilder {
         ))
     }
 
+    fn tuple(
+        &'a self,
+        entries: &'a [Tracked<ast::Entity>],
+        meta: &mut Meta,
+    ) -> Result<(MaybeType, ir::Entity), ParseError> {
+        let mut types = Vec::with_capacity(entries.len());
+        let mut buf = Vec::with_capacity(entries.len());
+        for entry in entries.iter() {
+            let (t, v) = self.build(entry, meta)?;
+            types.push(t.unwrap());
+            buf.push(v);
+        }
+        Ok((
+            MaybeType::Known(Type::Tuple(types)),
+            ir::Entity::ConstructTuple(buf),
+        ))
+    }
+
     fn if_expression(
         &'a self,
         branches: &'a [(Tracked<ast::Entity>, Tracked<ast::Entity>)],
@@ -436,9 +1039,13 @@ impl<'a> IrBuilder {
         branch_types.push(else_t.unwrap());
 
         // Verify that all the branches are the same type
-        if branch_types.iter().all(|t| *t == branch_types[0]) {
+        let merged = branch_types
+            .iter()
+            .skip(1)
+            .try_fold(branch_types[0].clone(), |acc, t| acc.merge(t));
+        if let Some(t) = merged {
             Ok((
-                MaybeType::Known(branch_types.remove(0)),
+                MaybeType::Known(t),
                 ir::Entity::IfExpression(ir::If::from(buf)),
             ))
         } else {
@@ -469,6 +1076,224 @@ impl<'a> IrBuilder {
         ))
     }
 
+    fn match_expression(
+        &'a self,
+        value: &'a Tracked<ast::Entity>,
+        arms: &'a [(Tracked<ast::Pattern>, Tracked<ast::Entity>)],
+        meta: &mut Meta,
+    ) -> Result<(MaybeType, ir::Entity), ParseError> {
+        let (value_t, value_v) = self.build(value, meta)?;
+        // Lambda parameters may not be inferred yet, `\(a, b) -> a` at least tells us the shape
+        if let MaybeType::Infer(inferred) = &value_t {
+            if inferred.borrow().is_none() {
+                *inferred.borrow_mut() = arms.iter().find_map(|(p, _)| pattern_shape(&p.inner));
+            }
+        }
+        let value_t = value_t.unwrap();
+
+        let mut buf = Vec::with_capacity(arms.len());
+        let mut arm_t: Option<Type> = None;
+        for (pattern, eval) in arms.iter() {
+            let mut bindings = Vec::new();
+            let ir_pattern = self
+                .pattern(meta.fid, pattern, &value_t, &mut bindings)
+                .map_err(|e| e.fallback_fid(meta.fid))?;
+
+            // Bound values are appended to the parameters of the current scope at runtime, so
+            // they're only visible for the evaluation of this arm.
+            let scope = meta.identifiers.len();
+            let base = meta.next_param();
+            for (i, (name, t)) in bindings.drain(0..).enumerate() {
+                meta.identifiers.push((
+                    name,
+                    IdentMeta {
+                        use_counter: 0,
+                        r#type: MaybeType::Known(t),
+                        ident: Identifiable::Param(base + i),
+                    },
+                ));
+            }
+            let (t, v) = self.build(eval, meta)?;
+            meta.identifiers.truncate(scope);
+
+            let t = t.unwrap();
+            match &arm_t {
+                None => arm_t = Some(t),
+                Some(expected) => match t.merge(expected) {
+                    Some(merged) => arm_t = Some(merged),
+                    None => {
+                        return Err(ParseFault::MatchArmTypeMismatch(Box::new((
+                            expected.clone(),
+                            t,
+                        )))
+                        .into_err(eval.pos())
+                        .fallback_fid(meta.fid));
+                    }
+                },
+            }
+            buf.push((ir_pattern, v));
+        }
+        let arm_t = arm_t.ok_or_else(|| {
+            ParseFault::MatchMissingArms
+                .into_err(value.pos())
+                .fallback_fid(meta.fid)
+        })?;
+        Ok((
+            MaybeType::Known(arm_t),
+            ir::Entity::Match(Box::new(ir::Match::new(value_v, buf))),
+        ))
+    }
+
+    // Verifies that the pattern can match values of type `t` and collects the names and types of
+    // the values it binds
+    fn pattern(
+        &'a self,
+        self_fid: usize,
+        pattern: &Tracked<ast::Pattern>,
+        t: &Type,
+        bindings: &mut Vec<(String, Type)>,
+    ) -> Result<ir::Pattern, ParseError> {
+        let mismatch = || {
+            ParseFault::PatternTypeMismatch(Box::new((pattern.inner.clone(), t.clone())))
+                .into_err(pattern.pos())
+        };
+        match &pattern.inner {
+            ast::Pattern::Wildcard => Ok(ir::Pattern::Wildcard),
+            ast::Pattern::Literal(inlined) => {
+                let lt: Type = inlined.into();
+                if lt != *t {
+                    return Err(mismatch());
+                }
+                Ok(ir::Pattern::Literal(inlined.clone().into()))
+            }
+            ast::Pattern::Ident(ident, params) => {
+                if let Type::KnownCustom(tfid, tid, args) = t {
+                    if let CustomType::Enum(r#enum) = &self.parser.modules[*tfid].types[*tid] {
+                        let generics = self.parser.modules[*tfid].types[*tid].generics_for(args);
+                        match r#enum.get_variant(&ident.inner.name) {
+                            Some((tag, payload)) => {
+                                let public = self.parser.modules[*tfid].types[*tid].is_public();
+                                if !self.parser.is_visible(self_fid, *tfid, public) {
+                                    return Err(ParseFault::NotExported(ident.clone())
+                                        .into_err(pattern.pos()));
+                                }
+                                if payload.len() != params.len() {
+                                    return Err(ParseFault::VariantParamAmountMismatch(
+                                        ident.inner.name.clone(),
+                                        payload.len(),
+                                        params.len(),
+                                    )
+                                    .into_err(pattern.pos()));
+                                }
+                                let mut inner = Vec::with_capacity(params.len());
+                                for (param, param_t) in params.iter().zip(payload.iter()) {
+                                    let param_t = self
+                                        .parser
+                                        .destruct_custom_type(*tfid, param_t.clone())
+                                        .map_err(|e| e.into_err(pattern.pos()))?
+                                        .decoded(&generics);
+                                    inner.push(self.pattern(self_fid, param, &param_t, bindings)?);
+                                }
+                                return Ok(ir::Pattern::Variant(tag, inner));
+                            }
+                            None => {
+                                let is_variant_name = ident
+                                    .inner
+                                    .name
+                                    .chars()
+                                    .next()
+                                    .map(|c| c.is_uppercase())
+                                    .unwrap_or(false);
+                                if is_variant_name || !params.is_empty() {
+                                    return Err(ParseFault::VariantNotFound(
+                                        ident.inner.name.clone(),
+                                        t.clone(),
+                                    )
+                                    .into_err(pattern.pos()));
+                                }
+                            }
+                        }
+                    }
+                }
+                if !params.is_empty() || !ident.inner.path.is_empty() {
+                    return Err(mismatch());
+                }
+                bindings.push((ident.inner.name.clone(), t.clone()));
+                Ok(ir::Pattern::Bind)
+            }
+            ast::Pattern::List(entries, rest) => {
+                let inner_t = match t {
+                    Type::List(box inner) => inner,
+                    _ => return Err(mismatch()),
+                };
+                let mut buf = Vec::with_capacity(entries.len());
+                for entry in entries.iter() {
+                    buf.push(self.pattern(self_fid, entry, inner_t, bindings)?);
+                }
+                let rest = match rest {
+                    Some(rest) => Some(Box::new(self.pattern(self_fid, rest, t, bindings)?)),
+                    None => None,
+                };
+                Ok(ir::Pattern::List(buf, rest))
+            }
+            ast::Pattern::Tuple(entries) => {
+                let entry_types = match t {
+                    Type::Tuple(entry_types) if entry_types.len() == entries.len() => entry_types,
+                    _ => return Err(mismatch()),
+                };
+                let mut buf = Vec::with_capacity(entries.len());
+                for (entry, entry_t) in entries.iter().zip(entry_types.iter()) {
+                    buf.push(self.pattern(self_fid, entry, entry_t, bindings)?);
+                }
+                Ok(ir::Pattern::Tuple(buf))
+            }
+        }
+    }
+
+    // Whether there are values of type `t` that the pattern doesn't match. Patterns of the wrong
+    // type are left for `pattern` to report.
+    fn refutable(&self, pattern: &ast::Pattern, t: &Type) -> bool {
+        match (pattern, t) {
+            (ast::Pattern::Wildcard, _) => false,
+            (ast::Pattern::Literal(_), _) => true,
+            (ast::Pattern::Ident(ident, params), Type::KnownCustom(tfid, tid, args)) => {
+                let custom = &self.parser.modules[*tfid].types[*tid];
+                let r#enum = match custom {
+                    CustomType::Enum(r#enum) => r#enum,
+                    CustomType::Struct(_) => return false,
+                };
+                match r#enum.get_variant(&ident.inner.name) {
+                    Some((_, payload)) => {
+                        let generics = custom.generics_for(args);
+                        r#enum.fields.len() > 1
+                            || params.iter().zip(payload.iter()).any(|(param, param_t)| {
+                                match self.parser.destruct_custom_type(*tfid, param_t.clone()) {
+                                    Ok(param_t) => {
+                                        self.refutable(&param.inner, &param_t.decoded(&generics))
+                                    }
+                                    Err(_) => false,
+                                }
+                            })
+                    }
+                    None => false,
+                }
+            }
+            (ast::Pattern::Ident(..), _) => false,
+            (ast::Pattern::List(entries, rest), _) => {
+                !entries.is_empty()
+                    || rest
+                        .as_ref()
+                        .map(|rest| self.refutable(&rest.inner, t))
+                        .unwrap_or(true)
+            }
+            (ast::Pattern::Tuple(entries), Type::Tuple(types)) => entries
+                .iter()
+                .zip(types.iter())
+                .any(|(entry, t)| self.refutable(&entry.inner, t)),
+            (ast::Pattern::Tuple(_), _) => false,
+        }
+    }
+
     fn wrap_into_lambda(
         &'a self,
         ident: Anot<Identifier, Type>,
@@ -538,7 +1363,7 @@ fn destruct_callable_ident(
         for (i, take) in takes.iter().enumerate() {
             match &param_types[i] {
                 MaybeType::Known(t) => {
-                    if *t != *take {
+                    if !t.fits(take) {
                         return Err(ParseFault::ParamCallMismatch(Box::new((
                             takes,
                             gives,
@@ -548,7 +1373,7 @@ fn destruct_callable_ident(
                 }
                 MaybeType::Infer(t) => {
                     if let Some(t) = t.borrow().as_ref() {
-                        if *t != *take {
+                        if !t.fits(take) {
                             return Err(ParseFault::ParamCallMismatch(Box::new((
                                 takes,
                                 gives,
@@ -569,3 +1394,28 @@ fn destruct_callable_ident(
         ))))
     }
 }
+
+// The type of values a pattern can match, as far as the pattern itself tells. Entries that don't
+// tell are left as generics.
+fn pattern_shape(pattern: &ast::Pattern) -> Option<Type> {
+    match pattern {
+        ast::Pattern::Literal(inlined) => Some(inlined.into()),
+        ast::Pattern::Tuple(entries) => Some(Type::Tuple(
+            entries
+                .iter()
+                .map(|entry| pattern_shape(&entry.inner).unwrap_or(Type::Generic(0)))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+// A field given twice has no obvious winner, so both record construction and updates reject it
+fn check_duplicate_fields(fields: &[(String, Tracked<ast::Entity>)]) -> Result<(), ParseError> {
+    for (i, (name, entity)) in fields.iter().enumerate() {
+        if fields[..i].iter().any(|(n, _)| n == name) {
+            return Err(ParseFault::FieldGivenTwice(name.clone()).into_err(entity.pos()));
+        }
+    }
+    Ok(())
+}
@@ -18,7 +18,7 @@ impl fmt::Display for Callable {
                 "(\\{} -> {})",
                 param_names
                     .iter()
-                    .map(|t| fmt::Display::to_string(t))
+                    .map(|t| t.to_string())
                     .collect::<Vec<_>>()
                     .join(" "),
                 body
@@ -55,7 +55,7 @@ impl fmt::Display for Passable {
                 "#(\\{} -> {})",
                 param_names
                     .iter()
-                    .map(|t| fmt::Display::to_string(t))
+                    .map(|t| t.to_string())
                     .collect::<Vec<_>>()
                     .join(" "),
                 body
@@ -64,6 +64,97 @@ impl fmt::Display for Passable {
     }
 }
 
+// Left-hand side of a match arm. Whether an identifier is an enum variant or a new binding
+// depends on the type being matched on, so that's decided by the checker.
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    Wildcard,
+    Literal(Inlinable),
+    Ident(Anot<Identifier, Type>, Vec<Tracked<Pattern>>),
+    List(Vec<Tracked<Pattern>>, Option<Box<Tracked<Pattern>>>),
+    Tuple(Vec<Tracked<Pattern>>),
+}
+
+impl Pattern {
+    // The names this pattern binds, for destructuring outside of match expressions. Like the
+    // checker does, capitalized identifiers are assumed to be enum variants.
+    pub fn bound_names(&self, names: &mut Vec<String>) {
+        match self {
+            Pattern::Ident(ident, params) if params.is_empty() => {
+                let is_variant_name = ident
+                    .inner
+                    .name
+                    .chars()
+                    .next()
+                    .map(|c| c.is_uppercase())
+                    .unwrap_or(false);
+                if !is_variant_name && ident.inner.path.is_empty() {
+                    names.push(ident.inner.name.clone());
+                }
+            }
+            Pattern::Ident(_, params) | Pattern::Tuple(params) => {
+                params.iter().for_each(|p| p.inner.bound_names(names))
+            }
+            Pattern::List(entries, rest) => {
+                entries.iter().for_each(|p| p.inner.bound_names(names));
+                if let Some(rest) = rest {
+                    rest.inner.bound_names(names);
+                }
+            }
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Wildcard => f.write_str("_"),
+            Pattern::Literal(v) => write!(f, "{}", v),
+            Pattern::Ident(ident, params) => {
+                if params.is_empty() {
+                    write!(f, "{}", ident)
+                } else {
+                    write!(
+                        f,
+                        "({} {})",
+                        ident,
+                        params
+                            .iter()
+                            .map(|t| t.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )
+                }
+            }
+            Pattern::List(entries, rest) => {
+                write!(
+                    f,
+                    "[{}",
+                    entries
+                        .iter()
+                        .map(|t| t.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+                if let Some(rest) = rest {
+                    write!(f, " | {}", rest)?;
+                }
+                write!(f, "]")
+            }
+            Pattern::Tuple(entries) => write!(
+                f,
+                "({})",
+                entries
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Entity {
     Call(Callable, Vec<Tracked<Entity>>),
@@ -73,9 +164,25 @@ pub enum Entity {
         Box<Tracked<Entity>>,
     ),
     First(Vec<Tracked<Entity>>),
+    Match(
+        Box<Tracked<Entity>>,
+        Vec<(Tracked<Pattern>, Tracked<Entity>)>,
+    ),
     Record(Anot<Identifier, Type>, Vec<(String, Tracked<Entity>)>),
+    RecordUpdate(Box<Tracked<Entity>>, Vec<(String, Tracked<Entity>)>),
+    FieldAccess(Box<Tracked<Entity>>, String),
+    // Unwraps `Just`/`Ok` and returns early from the function on `None`/`Err`
+    Try(Box<Tracked<Entity>>),
+    // `let x = value in body`, the value is evaluated once before the body
+    Let(String, Box<Tracked<Entity>>, Box<Tracked<Entity>>),
+    // Short-circuiting, the right side is only evaluated when needed
+    And(Box<Tracked<Entity>>, Box<Tracked<Entity>>),
+    Or(Box<Tracked<Entity>>, Box<Tracked<Entity>>),
+    // Ends the program with the given status code
+    Exit(Box<Tracked<Entity>>),
     Lambda(Vec<Anot<Identifier, Type>>, Box<Tracked<Entity>>),
     List(Vec<Tracked<Entity>>),
+    Tuple(Vec<Tracked<Entity>>),
     Inlined(Inlinable),
     SingleIdent(Anot<Identifier, Type>),
 
@@ -110,7 +217,7 @@ impl fmt::Display for Entity {
                 c,
                 params
                     .iter()
-                    .map(|t| fmt::Display::to_string(t))
+                    .map(|t| t.to_string())
                     .collect::<Vec<_>>()
                     .join(" ")
             ),
@@ -130,11 +237,20 @@ impl fmt::Display for Entity {
                 "(first {} then {})",
                 branches[0..branches.len() - 1]
                     .iter()
-                    .map(|t| fmt::Display::to_string(t))
+                    .map(|t| t.to_string())
                     .collect::<Vec<_>>()
                     .join(" and "),
                 branches.last().unwrap()
             ),
+            Entity::Match(value, arms) => write!(
+                f,
+                "(match {} {})",
+                value,
+                arms.iter()
+                    .map(|(pattern, eval)| format!("| {}: {}", pattern, eval))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
             Entity::Record(name, fields) => write!(
                 f,
                 "{{ {} . {} }}",
@@ -145,12 +261,28 @@ impl fmt::Display for Entity {
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
+            Entity::RecordUpdate(record, fields) => write!(
+                f,
+                "{{ {} }} << {}",
+                fields
+                    .iter()
+                    .map(|entity| format!("{} {}", entity.0, entity.1))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                record
+            ),
+            Entity::FieldAccess(record, field) => write!(f, "{}.{}", record, field),
+            Entity::Try(inner) => write!(f, "(try {})", inner),
+            Entity::Exit(code) => write!(f, "(exit {})", code),
+            Entity::Let(name, value, body) => write!(f, "(let {} = {} in {})", name, value, body),
+            Entity::And(left, right) => write!(f, "({} and {})", left, right),
+            Entity::Or(left, right) => write!(f, "({} or {})", left, right),
             Entity::Lambda(param_names, body) => write!(
                 f,
                 "(\\{} -> {})",
                 param_names
                     .iter()
-                    .map(|t| fmt::Display::to_string(t))
+                    .map(|t| t.to_string())
                     .collect::<Vec<_>>()
                     .join(" "),
                 body
@@ -160,7 +292,16 @@ impl fmt::Display for Entity {
                 "[{}]",
                 entries
                     .iter()
-                    .map(|t| fmt::Display::to_string(t))
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Entity::Tuple(entries) => write!(
+                f,
+                "({})",
+                entries
+                    .iter()
+                    .map(|t| t.to_string())
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
@@ -8,9 +8,19 @@ use std::hash::{Hash, Hasher};
 pub enum Identifiable {
     Param(usize),
     Captured(usize),
+    // A value of the frame a lambda is created in, which is only captured once it's used
+    Capturable,
     Where((usize, usize), usize),
 }
 
+// What a lambda captures, in the order of the captured indexes
+#[derive(Hash, Clone, Eq, PartialEq, Debug)]
+pub enum Capture {
+    // Index into the identifiers of the frame the lambda is created in
+    Identifier(usize),
+    Where(String),
+}
+
 #[derive(Clone, Eq, Debug)]
 pub struct IdentMeta {
     pub r#type: MaybeType,
@@ -34,45 +44,57 @@ pub struct Meta {
     pub fid: usize,
     pub ident: Anot<Identifier, Attr>,
     pub return_type: Type,
+    // `return_type` as it was written, so that errors can refer to aliases by their name
+    pub declared_return: Type,
     pub identifiers: Vec<(String, IdentMeta)>,
+    // The amount of identifiers that belong to the function itself (parameters and `where` bindings)
+    pub scope: usize,
+    // Snapshot of the frame a lambda was created in, `None` outside of lambdas.
+    // `return_type` still refers to the surrounding function while inside of a lambda
+    pub outer: Option<Box<Meta>>,
+    // What this lambda captured so far
+    pub captured: Vec<Capture>,
+    // Runtime parameter slots taken by bindings that are hidden while a `where` binding is built
+    pub hidden_params: usize,
 }
 
 impl Meta {
+    // Searches from the back so that later bindings shadow earlier ones
     pub fn identifier(&self, name: &str) -> Option<&IdentMeta> {
         self.identifiers
             .iter()
+            .rev()
             .find_map(|(n, im)| if n == name { Some(im) } else { None })
     }
-    pub fn identifier_mut(&mut self, name: &str) -> Option<&mut IdentMeta> {
-        self.identifiers
-            .iter_mut()
-            .find_map(|(n, im)| if n == name { Some(im) } else { None })
+    // The parameter index that the next value bound inside of the function body will occupy
+    pub fn next_param(&self) -> usize {
+        self.hidden_params
+            + self
+                .identifiers
+                .iter()
+                .filter(|(_, im)| matches!(im.ident, Identifiable::Param(_)))
+                .count()
     }
-    // Modifies the `use_counter` of self by comparison, and dumps those of which were captured
-    // Used for lambda's
-    pub fn was_used(&mut self, other: &Meta) -> Vec<Capturable> {
-        let mut captured = Vec::new();
-        for (name, im) in self.identifiers.iter_mut() {
-            if let Identifiable::Param(n) = im.ident {
-                let newer_im = &other.identifier(name).unwrap();
-                if let Identifiable::Captured(_) = newer_im.ident {
-                    if newer_im.use_counter > 0 {
-                        // The parameter was used in the lambda! We need to capture it then
-                        im.use_counter += newer_im.use_counter;
-                        captured.push(Capturable::ParentParam(n));
-                    }
-                }
-            }
+    // What a lambda created in this frame captures for the identifier at `i`
+    pub fn capturable(&mut self, i: usize) -> Capturable {
+        match self.use_identifier(i).ident {
+            Identifiable::Param(n) => Capturable::ParentParam(n),
+            Identifiable::Captured(n) => Capturable::ParentLambda(n),
+            _ => unreachable!(),
         }
-        captured
     }
-    // Turns parameters into captured values and appends new parameters. This is used when encountering lambdas.
+    // Turns parameters and captured values into values that can be captured, and appends new
+    // parameters. This is used when encountering lambdas.
     pub fn lambda_swap(&mut self, params: &[Anot<Identifier, Type>], known_types: &[MaybeType]) {
-        let mut captured_n = 0;
+        self.outer = Some(Box::new(self.clone()));
+        self.captured = Vec::new();
+        self.hidden_params = 0;
+
         for (_, im) in self.identifiers.iter_mut() {
-            if let Identifiable::Param(_) = im.ident {
-                im.ident = Identifiable::Captured(captured_n);
-                captured_n += 1;
+            if let Identifiable::Param(_) | Identifiable::Captured(_) | Identifiable::Capturable =
+                im.ident
+            {
+                im.ident = Identifiable::Capturable;
                 im.use_counter = 0;
             }
         }
@@ -92,9 +114,20 @@ impl Meta {
     }
 
     pub fn try_use(&mut self, name: &str) -> Option<&IdentMeta> {
-        let identmeta = self.identifier_mut(name)?;
-        identmeta.use_counter += 1;
-        Some(identmeta)
+        let i = self.identifiers.iter().rposition(|(n, _)| n == name)?;
+        Some(self.use_identifier(i))
+    }
+
+    // Values of the frame a lambda was created in are only captured once they're used, so that
+    // the lambda doesn't hold on to more than it needs.
+    fn use_identifier(&mut self, i: usize) -> &IdentMeta {
+        if let Identifiable::Capturable = self.identifiers[i].1.ident {
+            self.identifiers[i].1.ident = Identifiable::Captured(self.captured.len());
+            self.captured.push(Capture::Identifier(i));
+        }
+        let im = &mut self.identifiers[i].1;
+        im.use_counter += 1;
+        im
     }
 
     pub fn identifiers_from(
@@ -73,6 +73,10 @@ impl ParseError {
                 );
                 return self;
             };
+            // The prelude is spread over several files so there's no single source to point into
+            if *filesource == FileSource::Prelude {
+                return self;
+            }
             let mut source_code = String::with_capacity(20);
             let path = filesource.to_pathbuf(&parser.environment);
             match File::open(&path) {
@@ -109,17 +113,23 @@ pub enum ParseFault {
     MissingRightSideOperator(Box<(RawToken, Operator, RawToken)>),
     EndedMissingRightSideOperator(RawToken, Operator),
     InvalidIdentifier(String, IdentSource),
+    UnmatchedQuote,
+    UnknownEscape(char),
     InvalidPath(Vec<String>),
     BridgedWrongPathLen(Vec<String>),
     BridgedFunctionNotFound(Anot<String, Type>),
     BridgedFunctionNoMode(u8),
     ParameterlessLambda,
     TypeNotFound(usize, Anot<Identifier, Type>),
+    TypeArgAmountMismatch(Anot<Identifier, Type>, usize),
+    TypeParamNotGeneric(Type),
+    AliasCycle(String),
     RecordWithEnum(usize, Anot<Identifier, Type>),
     Unexpected(RawToken),
     UnexpectedWantedParameter(RawToken),
     Unmatched(Key),
     ModuleNotImported(String),
+    ModuleAliasTaken(String),
     CannotInferType(char),
     InvalidClosure(ast::Entity),
     InvalidClosureT(RawToken),
@@ -145,8 +155,40 @@ pub enum ParseFault {
         )>,
     ),
     ListMissingClose,
+    MatchMissingArms,
+    MatchArmTypeMismatch(Box<(Type, Type)>),
+    PatternTypeMismatch(Box<(ast::Pattern, Type)>),
+    WherePatternRefutable(Box<(ast::Pattern, Type)>),
+    VariantNotFound(String, Type),
+    VariantParamAmountMismatch(String, usize, usize),
+    VariantParamMismatch(Box<(String, Vec<Type>, Vec<MaybeType>)>),
+    FieldNotFound(String, Vec<String>),
+    FieldAccessOnNonRecord(Type),
+    FieldTypeMismatch(Box<(String, Type, Type)>),
+    FieldMissing(String),
+    FieldGivenTwice(String),
+    TryOnNonFallible(Type),
+    TryReturnMismatch(Box<(Type, Type)>),
+    TryInLambda,
+    ExitCodeNotInt(Type),
+    LogicalNotBoolean(Key, Type),
+    ComparingFunctions(Type),
+    ComparingDifferentTypes(Box<(Type, Type)>),
+    TraitAlreadyDeclared(String),
+    TypeAlreadyDeclared(String),
+    VariantAlreadyDeclared(String),
+    NotExported(Anot<Identifier, Type>),
+    PubOnNonItem(RawToken),
+    TraitNotFound(Identifier),
+    TraitNotImplemented(Identifier, Type),
+    ConstraintOnNonGeneric(String),
+    BuiltinTypeMismatch(String, Type),
     OpNoIdent,
     OpWantedIdent(RawToken),
+    OpWantedPrecedence(RawToken),
+    OpPrefixWithFixity(String),
+    OpWrongArity(String, usize),
+    OpFixityMismatch(String),
     InvalidParameterName(String),
     PipeIntoVoid,
     EmptyListType,
@@ -226,6 +268,9 @@ impl fmt::Display for ParseError {
             BridgedFunctionNotFound(ident) => write!(f, "No bridged function named `{}`", ident),
             BridgedFunctionNoMode(c) => write!(f, "Bridged path mode doesn't exist, got `{}`", c),
             TypeNotFound(_fid, ident) => write!(f, "Type `{}` not found", ident.inner.name),
+            TypeArgAmountMismatch(ident, wanted) => write!(f, "`{}` takes {} type argument(s) but was given {}", ident.inner, wanted, ident.anot.len()),
+            AliasCycle(name) => write!(f, "The alias `{}` refers back to itself, so it never ends up at an actual type", name),
+            TypeParamNotGeneric(t) => write!(f, "Types are declared with generics such as `a` as their type parameters, but this one was given `{}`", t),
             RecordWithEnum(_fid, ident) => write!(f, "You're trying to construct a record however, `{}` is an enum and not a struct", ident),
             FunctionConversionRequiresAnnotation(ident, variants) => {
                 write!(f, "This function conversion requires a type annotation. I don't know which of these variants to use.\n  {}", variants.keys().map(|params| {
@@ -235,16 +280,16 @@ impl fmt::Display for ParseError {
             ParamCallMismatch(box (takes, _gives, got)) => {
 
                 write!(f, "The function call originating from this parameter has the wrong types of arguments\n wanted  {}\n but got {}", 
-                    format_function_parameter(Some(takes), NO),
-                    format_function_parameter(Some(got), NO),
+                    format_function_parameter(Some(&takes.iter().map(|t| named(parser, self.module_fid, t)).collect::<Vec<_>>()), NO),
+                    format_function_parameter(Some(&got.iter().map(|t| named_maybe(parser, self.module_fid, t)).collect::<Vec<_>>()), NO),
                     )
             }
             ParamCallAmountMismatch(box (takes, _gives, got)) => {
                 write!(f, "The function call originating from this parameter wanted {} argument(s) but got {}\n wanted {}\n but got {}",
                     takes.len(),
                     got.len(),
-                    format_function_parameter(Some(takes), NO),
-                    format_function_parameter(Some(got), NO),
+                    format_function_parameter(Some(&takes.iter().map(|t| named(parser, self.module_fid, t)).collect::<Vec<_>>()), NO),
+                    format_function_parameter(Some(&got.iter().map(|t| named_maybe(parser, self.module_fid, t)).collect::<Vec<_>>()), NO),
                     )
             }
             ParamCannotTakeParameters(box (got_t, params)) => {
@@ -318,6 +363,8 @@ impl fmt::Display for ParseError {
                 // TODO: This only shows from one module. 
                 let (fid, variants) = &parser.functions_named(*fid, ident).unwrap().matching[0];
                 let module = &parser.modules[*fid];
+                let shown = params.iter().map(|t| named_maybe(parser, self.module_fid, t)).collect::<Vec<_>>();
+                let wanted = |funcid: usize| module.functions[funcid].parameter_types.iter().map(|t| MaybeType::Known(named(parser, self.module_fid, t))).collect::<Vec<_>>();
                 
                 match variants.len() {
                     1 => {
@@ -345,24 +392,24 @@ impl fmt::Display for ParseError {
                             write!(
                                 f,
                                 "Type mismatch. Wanted `{}` but got {}\n {}\n {}",
-                                wanted_params[i],
-                                params[i],
-                                format_header(&ident.inner.name, ident.inner.kind.clone(), Some(params), None),
-                                format_header(&wfuncb.name.inner.name, ident.inner.kind.clone(), Some(wanted_params.iter().cloned().map(MaybeType::Known).collect::<Vec<_>>().as_slice()) ,None),
+                                named(parser, self.module_fid, &wfuncb.parameter_types[i]),
+                                shown[i],
+                                format_header(&ident.inner.name, ident.inner.kind.clone(), Some(&shown), None),
+                                format_header(&wfuncb.name.inner.name, ident.inner.kind.clone(), Some(&wanted(*funcid)), None),
                             )
                         } else {
                             write!(f, "No function named `{}` takes these parameters\n  {}\n perhaps you meant to use?\n  {}",
                                 &ident.inner.name,
-                                format_header(&ident.inner.name, ident.inner.kind.clone(), Some(&params), None),
-                                format_header(&wfuncb.name.inner.name, ident.inner.kind.clone(), Some(&wanted_params.iter().cloned().map(MaybeType::Known).collect::<Vec<_>>().as_slice()), None),
+                                format_header(&ident.inner.name, ident.inner.kind.clone(), Some(&shown), None),
+                                format_header(&wfuncb.name.inner.name, ident.inner.kind.clone(), Some(&wanted(*funcid)), None),
                                 )
                         }
                     }
                     _ => {
                         write!(f, "No function named `{}` takes these parameters\n  {}\n i did however find these variants\n  {}",
                             &ident.inner.name,
-                            format_header(&ident.inner.name, ident.inner.kind.clone(), Some(params.as_slice()), None),
-                            variants.iter().map(|params| format_header(&ident.inner.name, ident.inner.kind.clone(), Some(params.0.iter().cloned().map(MaybeType::Known).collect::<Vec<_>>().as_slice()), None)).collect::<Vec<String>>().join("\n  ")
+                            format_header(&ident.inner.name, ident.inner.kind.clone(), Some(&shown), None),
+                            variants.iter().map(|(_, funcid)| format_header(&ident.inner.name, ident.inner.kind.clone(), Some(&wanted(*funcid)), None)).collect::<Vec<String>>().join("\n  ")
                             )
                     },
                 }
@@ -395,6 +442,8 @@ impl fmt::Display for ParseError {
                     IdentSource::Ident => Ok(()),
                 }
             },
+            UnmatchedQuote => write!(f, "This string literal is never closed, I was looking for a `\"` to end it"),
+            UnknownEscape(c) => write!(f, "`\\{}` is not an escape sequence I know of in this string literal\n perhaps you meant `\\\\{}`?", c, c),
             FirstMissingThen => {
                 write!(f, "This first statement doesn't have a `then` branch. I was looking for something ressembling\n first ...\n then  ...")
             },
@@ -415,13 +464,52 @@ impl fmt::Display for ParseError {
             IfConditionNotBoolean(box (_got, gott)) => write!(f, "The condition for this if branch isn't an boolean\n Wanted `bool` but got `{}`", gott),
             IfBranchTypeMismatch(box (types, (branches, else_do))) => write!(f, "ERROR TODO: Properly display if statement typing.\n These branches don't return the same value\n{:?}", types),
             ListMissingClose => write!(f, "This list open is missing a matching `]` to close it"),
+            MatchMissingArms => write!(f, "This match expression doesn't have any arms, I was looking for something ressembling\n match ...\n  | pattern: ...\n  | _: ..."),
+            MatchArmTypeMismatch(box (expected, got)) => write!(f, "This match arm doesn't return the same type as the previous ones\n Expected `{}`\n But got `{}`", named(parser, self.module_fid, expected), named(parser, self.module_fid, got)),
+            PatternTypeMismatch(box (pattern, t)) => write!(f, "The pattern `{}` can never match a value of type `{}`", pattern, named(parser, self.module_fid, t)),
+            WherePatternRefutable(box (pattern, t)) => write!(f, "The pattern `{}` of this where binding doesn't match every value of type `{}`, values that might not match need a match expression instead", pattern, named(parser, self.module_fid, t)),
+            VariantNotFound(name, t) => write!(f, "`{}` doesn't have a variant named `{}`", named(parser, self.module_fid, t), name),
+            VariantParamAmountMismatch(name, wanted, got) => write!(f, "The variant `{}` holds {} value(s) but was given {}", name, wanted, got),
+            VariantParamMismatch(box (name, takes, got)) => {
+                write!(f, "The variant `{}` was given the wrong types of values\n wanted  {}\n but got {}",
+                    name,
+                    format_function_parameter(Some(&takes.iter().map(|t| named(parser, self.module_fid, t)).collect::<Vec<_>>()), NO),
+                    format_function_parameter(Some(&got.iter().map(|t| named_maybe(parser, self.module_fid, t)).collect::<Vec<_>>()), NO),
+                    )
+            }
+            FieldNotFound(name, available) => write!(f, "This record doesn't have a field named `{}`, the available fields are\n  {}", name, available.join("\n  ")),
+            FieldAccessOnNonRecord(t) => write!(f, "Only records have fields, but this is a value of type `{}`", named(parser, self.module_fid, t)),
+            FieldTypeMismatch(box (name, wanted, got)) => write!(f, "The field `{}` is of type `{}` but it was given a `{}`", name, named(parser, self.module_fid, wanted), named(parser, self.module_fid, got)),
+            FieldMissing(name) => write!(f, "This record is missing the field `{}`, which doesn't have a default value", name),
+            FieldGivenTwice(name) => write!(f, "The field `{}` was given more than once", name),
+            ExitCodeNotInt(t) => write!(f, "`exit` takes the status code to exit the program with, which needs to be an `int` but this is a value of type `{}`", named(parser, self.module_fid, t)),
+            TryOnNonFallible(t) => write!(f, "`try` can only be used on `maybe` and `result` values, but this is a value of type `{}`", named(parser, self.module_fid, t)),
+            TryReturnMismatch(box (t, returns)) => write!(f, "`try` on a value of type `{}` returns it early on failure, but this function returns `{}`", named(parser, self.module_fid, t), named(parser, self.module_fid, returns)),
+            TryInLambda => write!(f, "`try` can't be used inside of lambdas since it would return from the lambda instead of the function"),
+            ComparingFunctions(t) => write!(f, "Functions can't be compared, so neither can values of type `{}`", named(parser, self.module_fid, t)),
+            ComparingDifferentTypes(box (left, right)) => write!(f, "Only values of the same type can be compared, but this compares `{}` with `{}`", named(parser, self.module_fid, left), named(parser, self.module_fid, right)),
+            TraitAlreadyDeclared(name) => write!(f, "The trait `{}` has already been declared in this module", name),
+            NotExported(ident) => write!(f, "`{}` isn't exported by its module, it needs to be declared with `pub` to be used from other modules", ident),
+            PubOnNonItem(token) => write!(f, "Only `fn`, `operator`, `type`, `enum` and `alias` can be made public, but `pub` was followed by `{}`", token),
+            TypeAlreadyDeclared(name) => write!(f, "A type or alias named `{}` has already been declared in this module", name),
+            VariantAlreadyDeclared(name) => write!(f, "An enum variant named `{}` has already been declared in this module", name),
+            TraitNotFound(ident) => write!(f, "Trait `{}` not found", ident),
+            TraitNotImplemented(r#trait, t) => write!(f, "This function requires `{}` but there's no `impl {} {}`", r#trait, r#trait, named(parser, self.module_fid, t)),
+            ConstraintOnNonGeneric(name) => write!(f, "Only generics can be constrained by traits, but `{}` isn't a generic", name),
+            BuiltinTypeMismatch(name, t) => write!(f, "builtin:{} only works on `int` and `float`, but was given a value of type `{}`", name, named(parser, self.module_fid, t)),
+            LogicalNotBoolean(key, t) => write!(f, "Both sides of `{}` need to be booleans\n Wanted `bool` but got `{}`", key, named(parser, self.module_fid, t)),
             OpNoIdent => write!(f, "You need to provide an identifier for this operator"),
             OpWantedIdent(a) => write!(f, "Wanted identifier for the operator but got `{}`", a),
+            OpWantedPrecedence(a) => write!(f, "Wanted a precedence from 0 to 9 for the operator but got `{}`", a),
+            OpPrefixWithFixity(name) => write!(f, "`{}` takes one parameter so it's a prefix operator, which can't have a precedence\n perhaps you meant to remove the `infixl` or `infixr`?", name),
+            OpWrongArity(name, n) => write!(f, "Operators take one parameter as prefix or two as infix, but `{}` takes {}", name, n),
+            OpFixityMismatch(name) => write!(f, "The operator `{}` has already been declared with a different precedence", name),
             InvalidParameterName(name) => write!(f, "`{}` is not a valid identifier for a parmater", name),
             PipeIntoVoid => write!(f, "This pipe doesn't lead to anywhere, perhaps you need to remove it?"),
             EmptyListType => write!(f, "I know that this is a list but you need to say what type the contents of the list will be\n such as [a] or [int]"),
             UnexpectedWantedParameter(got) => write!(f, "I was expecting to see something to use as parameter but got `{}`", got),
             ModuleNotImported(mod_name) => write!(f, "Module `{}` is not imported", mod_name),
+            ModuleAliasTaken(name) => write!(f, "The name `{}` is already used for another imported module, you can pick a different one with `use ... as name`", name),
             FnTypeReturnMismatch(meta, got) => {
                 let p_types = meta.identifiers.iter().filter_map(|(_name, im)| {
                     if let Identifiable::Param(_n) = im.ident {
@@ -430,14 +518,14 @@ impl fmt::Display for ParseError {
                 }).collect::<Vec<MaybeType>>();
                 // let p_types = funcb.parameter_types.iter().cloned().map(MaybeType::Known).collect::<Vec<_>>();
                 write!(f, "This function returns the wrong value. Acording to its type signature it should return `{}`\n  {}\nbut instead it returns `{}`",
-                meta.return_type,
+                meta.declared_return,
                 format_header(&meta.ident.inner.name, meta.ident.inner.kind.clone(),
                 if p_types.is_empty() { 
                     None 
                 } else { 
                     Some(p_types.as_slice()) 
                 }, 
-                Some(&meta.return_type)),
+                Some(&meta.declared_return)),
                 got,
             )},
             Internal => write!(f, "Internal leaf error"),
@@ -445,6 +533,59 @@ impl fmt::Display for ParseError {
     }
 }
 
+// Records and enums are only known by the ids of their declarations once checked, so they're shown
+// by their declared name instead. Types declared in another module than the one the error is in
+// are prefixed by the name of that module, such as `shapes:shape`.
+fn named(parser: &Parser, fid: Option<usize>, t: &Type) -> Type {
+    match t {
+        Type::KnownCustom(tfid, tid, args) => {
+            let module = &parser.modules[*tfid];
+            let name = module
+                .type_ids
+                .iter()
+                .find(|(_, id)| **id == *tid)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_else(|| tid.to_string());
+            let path = match &module.module_path {
+                FileSource::Project(levels) | FileSource::Leafpath(levels)
+                    if Some(*tfid) != fid =>
+                {
+                    levels
+                        .last()
+                        .map(|level| level.trim_end_matches(".lf").to_string())
+                        .into_iter()
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            let ident = Identifier {
+                path,
+                name,
+                kind: IdentifierType::Normal,
+            };
+            let args = args.iter().map(|t| named(parser, fid, t)).collect();
+            Type::Custom(Anot::from((ident, args)))
+        }
+        Type::List(box inner) => Type::List(Box::new(named(parser, fid, inner))),
+        Type::Tuple(entries) => Type::Tuple(entries.iter().map(|t| named(parser, fid, t)).collect()),
+        Type::Function(box (takes, gives)) => Type::Function(Box::new((
+            takes.iter().map(|t| named(parser, fid, t)).collect(),
+            named(parser, fid, gives),
+        ))),
+        _ => t.clone(),
+    }
+}
+
+fn named_maybe(parser: &Parser, fid: Option<usize>, t: &MaybeType) -> MaybeType {
+    match t {
+        MaybeType::Known(t) => MaybeType::Known(named(parser, fid, t)),
+        MaybeType::Infer(inferred) => match inferred.borrow().as_ref() {
+            Some(t) => MaybeType::Known(named(parser, fid, t)),
+            None => t.clone(),
+        },
+    }
+}
+
 fn format_function_parameter<A: fmt::Display, B: fmt::Display>(
     params: Option<&[A]>,
     returns: Option<&B>,
@@ -566,9 +707,18 @@ fn describe(entity: &ast::Entity) -> &str {
         Entity::First(_) => "first statement",
         Entity::Lambda(_, _) => "lambda",
         Entity::List(_) => "list",
+        Entity::Tuple(_) => "tuple",
         Entity::Inlined(_) => "value",
         Entity::SingleIdent(_) => "identifier",
         Entity::Unimplemented => "unimplemented",
         Entity::Record(_, _) => "record",
+        Entity::FieldAccess(_, _) => "field access",
+        Entity::RecordUpdate(_, _) => "record update",
+        Entity::Match(_, _) => "match expression",
+        Entity::Try(_) => "try expression",
+        Entity::Exit(_) => "exit",
+        Entity::Let(_, _, _) => "let expression",
+        Entity::And(_, _) => "and expression",
+        Entity::Or(_, _) => "or expression",
     }
 }
@@ -1,7 +1,8 @@
 use super::{
-    ast, ast::AstBuilder, tokenizer::TokenSource, Anot, Attr, Identifier, Key, ParseError,
+    ast, ast::AstBuilder, tokenizer::TokenSource, Anot, Attr, Fixity, Identifier, Key, ParseError,
     ParseFault, RawToken, Tokenizer, Tracked, Type,
 };
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::hash::Hash;
@@ -14,9 +15,18 @@ pub struct FunctionBuilder {
     pub name: Anot<Identifier, Attr>,
     pub parameter_names: Vec<String>,
     pub parameter_types: Vec<Type>,
+    // `(a: num)` in the signature, the generic along with the trait it needs to implement and
+    // where that trait was written
+    pub constraints: Vec<(u8, Identifier, usize)>,
     pub returns: Type,
+    // Where each of `parameter_types` followed by `returns` was written, if they were
+    pub type_positions: Vec<usize>,
     pub body: Tracked<ast::Entity>,
     pub wheres: Vec<(String, Tracked<ast::Entity>)>,
+    // Only set for operators that declare their precedence
+    pub fixity: Option<Fixity>,
+    // Declared with `pub`, otherwise it can't be used from other modules
+    pub public: bool,
 }
 
 impl PartialEq for FunctionBuilder {
@@ -44,9 +54,13 @@ impl FunctionBuilder {
             name: Anot::default(),
             parameter_names: Vec::new(),
             parameter_types: Vec::new(),
+            constraints: Vec::new(),
             returns: Type::default(),
+            type_positions: Vec::new(),
             body: Tracked::default(),
             wheres: Vec::new(),
+            fixity: None,
+            public: false,
         }
     }
 
@@ -138,13 +152,18 @@ impl FunctionBuilder {
             };
             let source_index = next.pos();
             match next.inner {
-                RawToken::Identifier(ident) => self.parameter_types.push(
-                    Type::try_from(ident.inner.to_string().as_str())
-                        .map_err(|e| e.into_err(source_index))?,
-                ),
-                RawToken::Key(Key::ListOpen) => self
-                    .parameter_types
-                    .push(Type::List(Box::new(self.parse_list_type(tokenizer)?))),
+                RawToken::Identifier(ident) => {
+                    self.parameter_types.push(
+                        Type::try_from(ident.to_string().as_str())
+                            .map_err(|e| e.into_err(source_index))?,
+                    );
+                    self.type_positions.push(source_index);
+                }
+                RawToken::Key(Key::ListOpen) => {
+                    let t = Type::List(Box::new(self.parse_list_type(tokenizer)?));
+                    self.parameter_types.push(t);
+                    self.type_positions.push(source_index);
+                }
                 RawToken::Key(Key::ParenClose) => {
                     if self.parameter_types.len() == 1 {
                         self.returns = self.parameter_types.pop().unwrap();
@@ -152,9 +171,19 @@ impl FunctionBuilder {
                         return Ok(self);
                     }
                 }
-                RawToken::Key(Key::ParenOpen) => self
-                    .parameter_types
-                    .push(Type::Function(Box::new(self.parse_param_type(tokenizer)?))),
+                RawToken::Key(Key::ParenOpen)
+                    if self.parameter_types.is_empty()
+                        && self.constraints.is_empty()
+                        && tokenizer.peek_second().map(|t| &t.inner)
+                            == Some(&RawToken::Key(Key::Colon)) =>
+                {
+                    self.parse_constraints(tokenizer)?
+                }
+                RawToken::Key(Key::ParenOpen) => {
+                    let t = self.parse_param_type(tokenizer)?;
+                    self.parameter_types.push(t);
+                    self.type_positions.push(source_index);
+                }
                 RawToken::Key(Key::Arrow) => return self.with_return(tokenizer),
                 _ => {
                     return ParseFault::GotButExpected(next.inner, self.err_type_expecting())
@@ -165,6 +194,80 @@ impl FunctionBuilder {
         }
     }
 
+    // `(a: num, b: eq ord)`, each generic can be given several traits
+    fn parse_constraints<I: Iterator<Item = char>>(
+        &mut self,
+        tokenizer: &mut Tokenizer<I>,
+    ) -> Result<(), ParseError> {
+        loop {
+            let (first, pos) = match tokenizer.next() {
+                Some(t) => t.sep(),
+                None => {
+                    return ParseFault::EndedWhileExpecting(vec!["generic".into()])
+                        .into_err(tokenizer.position - 1)
+                        .into()
+                }
+            };
+            let generic = match first {
+                RawToken::Identifier(ident) => match Type::try_from(ident.to_string().as_str()) {
+                    Ok(Type::Generic(n)) => n,
+                    _ => {
+                        return ParseFault::ConstraintOnNonGeneric(ident.to_string())
+                            .into_err(pos)
+                            .into()
+                    }
+                },
+                other => {
+                    return ParseFault::GotButExpected(other, vec!["generic".into()])
+                        .into_err(pos)
+                        .into()
+                }
+            };
+            match tokenizer.next().map(|t| t.sep()) {
+                Some((RawToken::Key(Key::Colon), _)) => {}
+                Some((other, pos)) => {
+                    return ParseFault::GotButExpected(other, vec![":".into()])
+                        .into_err(pos)
+                        .into()
+                }
+                None => {
+                    return ParseFault::EndedWhileExpecting(vec![":".into()])
+                        .into_err(pos)
+                        .into()
+                }
+            }
+            let mut traits = 0;
+            loop {
+                let (next, pos) = match tokenizer.next() {
+                    Some(t) => t.sep(),
+                    None => {
+                        return ParseFault::EndedWhileExpecting(vec!["trait".into()])
+                            .into_err(tokenizer.position - 1)
+                            .into()
+                    }
+                };
+                match next {
+                    RawToken::Identifier(ident) => {
+                        self.constraints.push((generic, ident.inner, pos));
+                        traits += 1;
+                    }
+                    RawToken::Key(Key::Comma) if traits > 0 => break,
+                    RawToken::Key(Key::ParenClose) if traits > 0 => return Ok(()),
+                    other => {
+                        let expected = if traits > 0 {
+                            vec!["trait".into(), ",".into(), ")".into()]
+                        } else {
+                            vec!["trait".into()]
+                        };
+                        return ParseFault::GotButExpected(other, expected)
+                            .into_err(pos)
+                            .into();
+                    }
+                }
+            }
+        }
+    }
+
     fn parse_list_type<I: Iterator<Item = char>>(
         &self,
         tokenizer: &mut Tokenizer<I>,
@@ -180,8 +283,9 @@ impl FunctionBuilder {
         let source_index = next.pos();
         let r#type = match next.inner {
             RawToken::Key(Key::ListOpen) => Type::List(Box::new(self.parse_list_type(tokenizer)?)),
+            RawToken::Key(Key::ParenOpen) => self.parse_param_type(tokenizer)?,
             RawToken::Identifier(ident) => {
-                Type::try_from(ident.inner.name.as_str()).map_err(|e| e.into_err(source_index))?
+                Type::try_from(ident.to_string().as_str()).map_err(|e| e.into_err(source_index))?
             }
             _ => {
                 return ParseFault::Unmatched(Key::ParenOpen)
@@ -204,11 +308,13 @@ impl FunctionBuilder {
         }
     }
 
+    // Either a function type such as `(a -> b)` or a tuple type such as `(int, string)`
     fn parse_param_type<I: Iterator<Item = char>>(
         &self,
         tokenizer: &mut Tokenizer<I>,
-    ) -> Result<(Vec<Type>, Type), ParseError> {
+    ) -> Result<Type, ParseError> {
         let mut buf = Vec::new();
+        let mut entries = Vec::new();
         loop {
             let next = match tokenizer.next() {
                 Some(t) => t,
@@ -221,25 +327,33 @@ impl FunctionBuilder {
             let source_index = next.pos();
             match next.inner {
                 RawToken::Identifier(ident) => buf.push(
-                    Type::try_from(ident.inner.name.as_str())
+                    Type::try_from(ident.to_string().as_str())
                         .map_err(|e| e.into_err(source_index))?,
                 ),
                 RawToken::Key(Key::ListOpen) => {
                     buf.push(Type::List(Box::new(self.parse_list_type(tokenizer)?)))
                 }
-                RawToken::Key(Key::ParenOpen) => {
-                    buf.push(Type::Function(Box::new(self.parse_param_type(tokenizer)?)))
+                RawToken::Key(Key::ParenOpen) => buf.push(self.parse_param_type(tokenizer)?),
+                RawToken::Key(Key::Arrow) if entries.is_empty() => {
+                    let returns = self.parse_return_type(tokenizer)?;
+                    return Ok(Type::Function(Box::new((buf, returns))));
                 }
-                RawToken::Key(Key::Arrow) => {
-                    let returns = (buf, self.parse_return_type(tokenizer)?);
-                    return Ok(returns);
+                RawToken::Key(Key::Comma) if buf.len() == 1 => entries.push(buf.pop().unwrap()),
+                RawToken::Key(Key::ParenClose) if !entries.is_empty() => {
+                    if buf.len() != 1 {
+                        return ParseFault::GotButExpected(next.inner, vec!["type".into()])
+                            .into_err(source_index)
+                            .into();
+                    }
+                    entries.push(buf.pop().unwrap());
+                    return Ok(Type::Tuple(entries));
                 }
                 RawToken::Key(Key::ParenClose) => {
                     if buf.len() > 1 {
                         panic!("ET: Malformed parameter type");
                     }
                     match buf.pop() {
-                        Some(returns) => return Ok((buf, returns)),
+                        Some(returns) => return Ok(Type::Function(Box::new((buf, returns)))),
                         None => panic!("ET: Empty parameter type (no return type is not allowed)"),
                     }
                 }
@@ -252,36 +366,43 @@ impl FunctionBuilder {
         }
     }
 
-    fn parse_return_type<I: Iterator<Item = char>>(
+    // A single complete type such as `int`, `[maybe<a>]` or `(int -> int)`
+    pub fn parse_type<I: Iterator<Item = char>>(
         &self,
         tokenizer: &mut Tokenizer<I>,
     ) -> Result<Type, ParseError> {
         let next = match tokenizer.next() {
             Some(t) => t,
             None => {
-                return ParseFault::EndedWhileExpecting(vec!["return type".into()])
+                return ParseFault::EndedWhileExpecting(vec!["type".into()])
                     .into_err(tokenizer.position - 1)
                     .into()
             }
         };
         let source_index = next.pos();
-        let r#type = match next.inner {
-            RawToken::Identifier(ident) => Type::try_from(ident.inner.to_string().as_str())
-                .map_err(|e| e.into_err(source_index))?,
-            RawToken::Key(Key::ParenOpen) => {
-                Type::Function(Box::new(self.parse_param_type(tokenizer)?))
+        match next.inner {
+            RawToken::Identifier(ident) => {
+                Type::try_from(ident.to_string().as_str()).map_err(|e| e.into_err(source_index))
             }
-            RawToken::Key(Key::ListOpen) => Type::List(Box::new(self.parse_list_type(tokenizer)?)),
-            _ => {
-                return ParseFault::GotButExpected(next.inner, vec!["return type".into()])
-                    .into_err(source_index)
-                    .into()
+            RawToken::Key(Key::ParenOpen) => self.parse_param_type(tokenizer),
+            RawToken::Key(Key::ListOpen) => {
+                Ok(Type::List(Box::new(self.parse_list_type(tokenizer)?)))
             }
-        };
+            _ => ParseFault::GotButExpected(next.inner, vec!["type".into()])
+                .into_err(source_index)
+                .into(),
+        }
+    }
+
+    fn parse_return_type<I: Iterator<Item = char>>(
+        &self,
+        tokenizer: &mut Tokenizer<I>,
+    ) -> Result<Type, ParseError> {
+        let r#type = self.parse_type(tokenizer)?;
         let after = match tokenizer.next() {
             None => {
                 return ParseFault::EndedWhileExpecting(vec![")".into()])
-                    .into_err(source_index)
+                    .into_err(tokenizer.position - 1)
                     .into()
             }
             Some(t) => t,
@@ -299,15 +420,21 @@ impl FunctionBuilder {
         mut self,
         tokenizer: &mut Tokenizer<I>,
     ) -> Result<Self, ParseError> {
+        let pos = tokenizer
+            .peek()
+            .map(|t| t.pos())
+            .unwrap_or(tokenizer.position);
         self.returns = self.parse_return_type(tokenizer)?;
+        self.type_positions.push(pos);
         Ok(self)
     }
 
     pub fn parse_body<I: Iterator<Item = char>>(
         &mut self,
         tokenizer: &mut Tokenizer<I>,
+        fixities: &HashMap<String, Fixity>,
     ) -> Result<(), ParseError> {
-        self.body = AstBuilder::new(tokenizer).run_chunk()?;
+        self.body = AstBuilder::new(tokenizer, fixities).run_chunk()?;
         while let Some(t) = tokenizer.peek() {
             match &t.inner {
                 RawToken::Header(_) => break,
@@ -317,7 +444,7 @@ impl FunctionBuilder {
                 }
                 RawToken::Key(Key::Where) => {
                     tokenizer.next();
-                    return self.parse_where(tokenizer);
+                    return self.parse_where(tokenizer, fixities);
                 }
                 other => return Err(ParseFault::Unexpected(other.clone()).into_err(t.pos())),
             }
@@ -328,6 +455,7 @@ impl FunctionBuilder {
     fn parse_where<I: Iterator<Item = char>>(
         &mut self,
         tokenizer: &mut Tokenizer<I>,
+        fixities: &HashMap<String, Fixity>,
     ) -> Result<(), ParseError> {
         tokenizer.skip_spaces_and_newlines();
         // Having a bar here is optional
@@ -339,12 +467,17 @@ impl FunctionBuilder {
             None => panic!("ET"),
             Some(f) => f.sep(),
         };
-        let name = if let RawToken::Identifier(ident) = first {
-            ident
-        } else {
-            return Err(
-                ParseFault::GotButExpected(first, vec!["where identifier".into()]).into_err(pos),
-            );
+        let name = match first {
+            RawToken::Identifier(ident) => Ok(ident.inner.name),
+            RawToken::Key(Key::ParenOpen) => {
+                Err(AstBuilder::new(tokenizer, fixities).run_paren_pattern(pos)?)
+            }
+            _ => {
+                return Err(
+                    ParseFault::GotButExpected(first, vec!["where identifier".into()])
+                        .into_err(pos),
+                )
+            }
         };
 
         match tokenizer.next() {
@@ -359,13 +492,36 @@ impl FunctionBuilder {
             }
         }
 
-        let mut builder = ast::AstBuilder::new(tokenizer);
+        let mut builder = ast::AstBuilder::new(tokenizer, fixities);
         let entity = builder.run_chunk()?;
-        self.wheres.push((name.inner.name, entity));
+        match name {
+            Ok(name) => self.wheres.push((name, entity)),
+            // `(q, r) = divmod 7 2` binds the value once to a hidden name that can't be written
+            // in source, and each name is a match on that binding.
+            Err(pattern) => {
+                let hidden = format!("({})", pattern.inner);
+                let value_pos = entity.pos();
+                self.wheres.push((hidden.clone(), entity));
+                let mut names = Vec::new();
+                pattern.inner.bound_names(&mut names);
+                for name in names {
+                    let ident = Anot::new(Identifier::try_from(name.as_str()).unwrap());
+                    let bound = Tracked::new(ast::Entity::SingleIdent(ident)).set(pattern.pos());
+                    let arms = vec![(pattern.clone(), bound)];
+                    let hidden = Anot::new(Identifier {
+                        name: hidden.clone(),
+                        ..Identifier::default()
+                    });
+                    let value = Tracked::new(ast::Entity::SingleIdent(hidden)).set(value_pos);
+                    let value = ast::Entity::Match(Box::new(value), arms);
+                    self.wheres.push((name, Tracked::new(value).set(value_pos)));
+                }
+            }
+        }
 
         tokenizer.skip_spaces_and_newlines();
         if let Some(RawToken::Key(Key::Bar)) = tokenizer.peek().map(|t| &t.inner) {
-            return self.parse_where(tokenizer);
+            return self.parse_where(tokenizer, fixities);
         }
 
         Ok(())
@@ -379,6 +535,23 @@ impl FunctionBuilder {
         }
         None
     }
+    // Destructured where bindings are evaluated once per call and shared by the names they bind
+    pub fn is_destructured_where(&self, whereid: usize) -> bool {
+        self.wheres[whereid].0.starts_with('(')
+    }
+    // The pattern of a destructured where binding, found in the match of any of its names
+    pub fn destructured_pattern(&self, whereid: usize) -> Option<&Tracked<ast::Pattern>> {
+        let hidden = &self.wheres[whereid].0;
+        self.wheres.iter().find_map(|(_, entity)| match &entity.inner {
+            ast::Entity::Match(box value, arms) => match &value.inner {
+                ast::Entity::SingleIdent(ident) if &ident.inner.name == hidden => {
+                    arms.first().map(|(pattern, _)| pattern)
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+    }
     pub fn get_where_from_ident(&self, ident: &[String]) -> Option<usize> {
         if ident.len() == 1 {
             return self.get_where(&ident[0]);
@@ -424,11 +597,19 @@ impl fmt::Debug for FunctionBuilder {
             .iter()
             .map(|t| t.to_string())
             .collect::<Vec<String>>();
-        let annotation = if types.is_empty() {
+        let mut annotation = if types.is_empty() {
             format!("{}", self.returns)
         } else {
             format!("{} -> {}", types.join(" "), self.returns)
         };
+        if !self.constraints.is_empty() {
+            let constraints = self
+                .constraints
+                .iter()
+                .map(|(n, r#trait, _)| format!("{}: {}", Type::Generic(*n), r#trait))
+                .collect::<Vec<String>>();
+            annotation = format!("({}) {}", constraints.join(", "), annotation);
+        }
         let where_statements = self
             .wheres
             .iter()
@@ -437,7 +618,8 @@ impl fmt::Debug for FunctionBuilder {
             .join("\n  ");
         write!(
             f,
-            "fn {} {} ({})\n{}",
+            "{}fn {} {} ({})\n{}",
+            if self.public { "pub " } else { "" },
             self.name,
             self.parameter_names.join(" "),
             annotation,
@@ -49,8 +49,8 @@ impl From<String> for Identifier {
     }
 }
 
-const OP_CHARS: &str = "!@#$%-+*/&?{}=;<>|";
-pub const NAME_CHARS: &str = "abcdefghijklmnopqrstuvwxyz123456789_-";
+pub const OP_CHARS: &str = "!@#$%-+*/&?{}=;<>|";
+pub const NAME_CHARS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_-";
 
 impl TryFrom<&str> for IdentifierType {
     type Error = ParseFault;
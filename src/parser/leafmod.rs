@@ -1,4 +1,4 @@
-use super::{Anot, CustomType, FunctionBuilder, Identifier, ParseFault, Type};
+use super::{Alias, Anot, CustomType, Fixity, FunctionBuilder, Identifier, ParseFault, Type};
 use crate::env::Environment;
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -23,8 +23,21 @@ pub struct ParseModule {
     pub type_ids: HashMap<String, usize>,
     pub types: Vec<CustomType>,
 
+    // Share the namespace of `type_ids` but are resolved away by `destruct_custom_type`
+    pub aliases: HashMap<String, Alias>,
+
+    // Traits only have a name, what they mean is up to the functions constraining generics by them
+    pub trait_ids: HashMap<String, usize>,
+    // `impl num int` along with where it's declared. The trait is resolved from this module once
+    // it's needed, after `Parser::check_declarations` made sure that it can be.
+    pub impls: Vec<(Identifier, Type, usize)>,
+
     pub imports: HashMap<String, usize>,
 
+    // Declared precedence of this module's operators, which are only visible from the module
+    // itself unless it's the prelude
+    pub fixities: HashMap<String, Fixity>,
+
     pub module_path: FileSource,
 }
 
@@ -35,15 +48,22 @@ impl ParseModule {
             functions: Vec::new(),
             type_ids: HashMap::new(),
             types: Vec::new(),
+            aliases: HashMap::new(),
+            trait_ids: HashMap::new(),
+            impls: Vec::new(),
             imports: HashMap::new(),
+            fixities: HashMap::new(),
             module_path,
         }
     }
-    pub fn get_import(&self, name: &str) -> Result<usize, ParseFault> {
+    // Imports are bound both by their alias and by their full path, so after `use std:list` the
+    // module can be referred to as either `list` or `std:list`
+    pub fn get_import(&self, path: &[String]) -> Result<usize, ParseFault> {
+        let name = path.join(":");
         self.imports
-            .get(name)
+            .get(&name)
             .cloned()
-            .ok_or_else(|| ParseFault::ModuleNotImported(name.to_owned()))
+            .ok_or(ParseFault::ModuleNotImported(name))
     }
 }
 
@@ -58,14 +78,15 @@ impl FileSource {
                 levels.push(next);
                 FileSource::Leafpath(levels)
             }
-            FileSource::Prelude => panic!("Use statements in prelude unsupported"),
+            // The prelude sits at the root of $LEAFPATH, so that's where its imports are relative to
+            FileSource::Prelude => FileSource::Leafpath(vec![next]),
         }
     }
     pub fn pop(&mut self) -> Option<String> {
         match self {
             FileSource::Project(levels) => levels.pop(),
             FileSource::Leafpath(levels) => levels.pop(),
-            FileSource::Prelude => panic!("Use statements in prelude unsupported"),
+            FileSource::Prelude => None,
         }
     }
 
@@ -81,7 +102,8 @@ impl FileSource {
                 path.set_extension("lf");
                 path
             }
-            FileSource::Prelude => panic!("Use statements in prelude unsupported"),
+            // The prelude is made up of every file in this directory
+            FileSource::Prelude => env.leafpath.join("prelude"),
         }
     }
 
@@ -94,18 +116,28 @@ impl FileSource {
     }
 
     // Create a new FileSource from the scope of self
-    // We search for filepath both from $LEAFPATH and relatively from entrypoint
-    pub fn fork_from(&self, ident: Anot<Identifier, Type>, env: &Environment) -> Self {
-        if self.is_entrypoint() {
-            FileSource::try_from((&ident, env)).unwrap()
-        } else {
+    // Modules other than the entrypoint first look for the file relatively from their own
+    // directory, after which we search both from $LEAFPATH and relatively from entrypoint
+    pub fn fork_from(
+        &self,
+        ident: Anot<Identifier, Type>,
+        env: &Environment,
+    ) -> Result<Self, ParseFault> {
+        if !self.is_entrypoint() {
             let mut new_module_path = self.clone();
             new_module_path.pop();
-            for level in ident.inner.path.into_iter() {
-                new_module_path = new_module_path.join(level);
+            for level in ident.inner.path.iter().chain(Some(&ident.inner.name)) {
+                new_module_path = new_module_path.join(level.clone());
+            }
+            if new_module_path.to_pathbuf(env).exists() {
+                return Ok(new_module_path);
             }
-            new_module_path
         }
+        FileSource::try_from((&ident, env)).map_err(|_| {
+            let mut entries = ident.inner.path.clone();
+            entries.push(ident.inner.name.clone());
+            ParseFault::ModuleLoadNotFound(entries)
+        })
     }
 }
 
@@ -127,9 +159,14 @@ impl TryFrom<(&Anot<Identifier, Type>, &Environment)> for FileSource {
     ) -> Result<FileSource, Self::Error> {
         let mut from_project_path = env.entrypoint.parent().unwrap().to_owned();
 
-        let mut file_postfix = ident.inner.path.join("/");
-        file_postfix.push('/');
-        file_postfix.push_str(&ident.inner.name);
+        let mut file_postfix = ident
+            .inner
+            .path
+            .iter()
+            .chain(Some(&ident.inner.name))
+            .cloned()
+            .collect::<Vec<String>>()
+            .join("/");
         file_postfix.push_str(".lf");
 
         from_project_path.push(&file_postfix);
@@ -156,7 +193,7 @@ impl TryFrom<(&Anot<Identifier, Type>, &Environment)> for FileSource {
             return Ok(FileSource::Leafpath(buf));
         }
 
-        panic!("ET: File {:?} not found", ident.inner.name);
+        Err(())
     }
 }
 
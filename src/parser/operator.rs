@@ -1,9 +1,33 @@
 use super::{
-    tokenizer::TokenSource, Attr, FunctionBuilder, IdentifierType, Key, ParseError, ParseFault,
-    RawToken, Tokenizer,
+    tokenizer::TokenSource, Attr, FunctionBuilder, IdentifierType, Inlinable, Key, ParseError,
+    ParseFault, RawToken, Tokenizer,
 };
 use std::convert::TryFrom;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+// `operator + infixl 6 (int int -> int)`
+//
+// Higher precedence binds tighter. Operators without a declaration are `infixl 9`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fixity {
+    pub associativity: Associativity,
+    pub precedence: u8,
+}
+
+impl Default for Fixity {
+    fn default() -> Self {
+        Fixity {
+            associativity: Associativity::Left,
+            precedence: 9,
+        }
+    }
+}
+
 // Operator reuses most of the function-construction methods
 
 impl FunctionBuilder {
@@ -31,6 +55,38 @@ impl FunctionBuilder {
                     .into();
             }
         };
+        self.with_fixity_operator(tokenizer)
+    }
+
+    fn with_fixity_operator<I: Iterator<Item = char>>(
+        mut self,
+        tokenizer: &mut Tokenizer<I>,
+    ) -> Result<Self, ParseError> {
+        let associativity = match tokenizer.peek().map(|t| &t.inner) {
+            Some(RawToken::Identifier(ident)) if ident.inner.name == "infixl" => {
+                Associativity::Left
+            }
+            Some(RawToken::Identifier(ident)) if ident.inner.name == "infixr" => {
+                Associativity::Right
+            }
+            _ => return self.with_types_operator(tokenizer),
+        };
+        tokenizer.next();
+        let precedence = match tokenizer.next().map(|t| t.sep()) {
+            Some((RawToken::Inlined(Inlinable::Int(n)), _)) if (0..10).contains(&n) => n as u8,
+            Some((other, pos)) => {
+                return ParseFault::OpWantedPrecedence(other).into_err(pos).into()
+            }
+            None => {
+                return ParseFault::EndedWhileExpecting(vec!["precedence".into()])
+                    .into_err(tokenizer.position - 1)
+                    .into()
+            }
+        };
+        self.fixity = Some(Fixity {
+            associativity,
+            precedence,
+        });
         self.with_types_operator(tokenizer)
     }
 
@@ -46,6 +102,7 @@ impl FunctionBuilder {
             }
             Some(t) => t,
         };
+        let types_pos = t.pos();
         match t.inner {
             RawToken::Key(Key::ParenOpen) => {}
             _ => {
@@ -56,10 +113,21 @@ impl FunctionBuilder {
             }
         }
         self = self.with_parameter_types(tokenizer)?;
-        if self.parameter_types.len() != 2 {
-            panic!("ET: Operator needs two parameters")
-        }
-        self.parameter_names = vec!["left".into(), "right".into()];
+        // Operators with a single parameter are used as prefix, such as `-x`
+        self.parameter_names = match self.parameter_types.len() {
+            1 if self.fixity.is_some() => {
+                return ParseFault::OpPrefixWithFixity(self.name.inner.name.clone())
+                    .into_err(types_pos)
+                    .into()
+            }
+            1 => vec!["right".into()],
+            2 => vec!["left".into(), "right".into()],
+            n => {
+                return ParseFault::OpWrongArity(self.name.inner.name.clone(), n)
+                    .into_err(types_pos)
+                    .into()
+            }
+        };
         Ok(self)
     }
 }
@@ -1,4 +1,7 @@
-use super::{ast, Anot, Identifier, MaybeType, ParseFault, Parser, Tracked, Type, PRELUDE_FID};
+use super::{
+    ast, Alias, Anot, CustomType, FunctionBuilder, Identifier, MaybeType, ParseFault, Parser,
+    Tracked, Type, PRELUDE_FID,
+};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 
@@ -10,20 +13,30 @@ impl Parser {
         params: &[MaybeType],
     ) -> Result<(usize, usize), ParseFault> {
         let variants = self.functions_named(self_fid, ident)?;
-        let mut matches = variants.keeping(|_, got| {
-            if got.len() == params.len() {
-                self.are_compatible(params, got)
-            } else {
-                false
+        // Reported instead if the only variants that fit were ruled out by their constraints
+        let mut unsatisfied = None;
+        let mut matches = variants.keeping(|fid, funcid, got| {
+            if got.len() != params.len() {
+                return false;
+            }
+            let generics = match self.compatible_generics(params, got) {
+                Some(generics) => generics,
+                None => return false,
+            };
+            let func = &self.modules[fid].functions[funcid];
+            match self.check_constraints(fid, func, &generics) {
+                Ok(()) => true,
+                Err(fault) => {
+                    unsatisfied.get_or_insert(fault);
+                    false
+                }
             }
         });
 
         match matches.len() {
-            0 => Err(ParseFault::FunctionVariantNotFound(
-                ident.clone(),
-                params.to_vec(),
-                self_fid,
-            )),
+            0 => Err(unsatisfied.unwrap_or_else(|| {
+                ParseFault::FunctionVariantNotFound(ident.clone(), params.to_vec(), self_fid)
+            })),
             1 => Ok(matches.remove(0)),
             _ => {
                 matches.sort_by(|left, right| self.least_generics(left, right));
@@ -41,17 +54,28 @@ impl Parser {
         let (fid, funcid) = self.find_func(self_fid, ident, params)?;
         let func = &self.modules[fid].functions[funcid];
 
-        infer_all(params, &func.parameter_types);
+        let parameter_types = func
+            .parameter_types
+            .iter()
+            .map(|t| self.destruct_custom_type(fid, t.clone()))
+            .collect::<Result<Vec<Type>, ParseFault>>()?;
+        infer_all(params, &parameter_types);
 
-        let mut return_type = func.returns.clone();
-        let generics = find_generics(&func.parameter_types, params).unwrap();
+        let mut return_type = self.destruct_custom_type(fid, func.returns.clone())?;
+        let generics = find_generics(&parameter_types, params).unwrap();
         swap_generic(&mut return_type, &generics);
+        let mut declared_return = func.returns.clone();
+        swap_generic(&mut declared_return, &generics);
 
+        let identifiers = ast::Meta::identifiers_from(fid, funcid, func, params);
         let meta = ast::Meta {
             fid,
+            scope: identifiers.len(),
             ident: func.name.clone(),
             return_type,
-            identifiers: ast::Meta::identifiers_from(fid, funcid, func, params),
+            declared_return,
+            identifiers,
+            ..ast::Meta::default()
         };
         Ok((&func.body, meta))
     }
@@ -67,7 +91,7 @@ impl Parser {
         let variants = self.functions_named(self_fid, ident)?;
 
         // let mut matches = matches_of(variants, |got| got.len() >= atleast_params);
-        let mut matches = variants.keeping(|_, got| got.len() >= atleast_params);
+        let mut matches = variants.keeping(|_, _, got| got.len() >= atleast_params);
         assert!(!matches.is_empty());
         let use_match = matches.remove(0);
 
@@ -94,25 +118,24 @@ impl Parser {
                 }
                 self_fid
             }
-            1 => {
-                match self.modules[self_fid]
-                    .imports
-                    .get(ident.inner.path.last().as_ref().unwrap().as_str())
-                {
-                    Some(fid) => *fid,
-                    None => {
-                        return Err(ParseFault::ModuleNotImported(
-                            ident.inner.path.last().cloned().unwrap(),
-                        ))
-                    }
-                }
-            }
-            _ => panic!("ET: Invalid path length"),
+            _ => self.modules[self_fid].get_import(&ident.inner.path)?,
         };
         let module = &self.modules[fid];
 
         if let Some(variants) = module.function_ids.get(&ident.inner.name) {
-            self.gather_to_and_deserialize(fid, &mut all_variants, variants);
+            if self.is_visible(self_fid, fid, false) {
+                self.gather_to_and_deserialize(fid, &mut all_variants, variants)?;
+            } else {
+                let exported = variants
+                    .iter()
+                    .filter(|(_, funcid)| module.functions[**funcid].public)
+                    .map(|(params, funcid)| (params.clone(), *funcid))
+                    .collect::<HashMap<Vec<Type>, usize>>();
+                if exported.is_empty() {
+                    return Err(ParseFault::NotExported(ident.clone()));
+                }
+                self.gather_to_and_deserialize(fid, &mut all_variants, &exported)?;
+            }
         };
         if all_variants.matching.is_empty() {
             return Err(ParseFault::FunctionNotFound(ident.clone(), self_fid));
@@ -120,12 +143,15 @@ impl Parser {
         Ok(all_variants)
     }
 
+    // Ties go to whichever was declared first, so that picking among overloads for parameters that
+    // aren't inferred yet is deterministic.
     fn least_generics(&self, left: &(usize, usize), right: &(usize, usize)) -> Ordering {
         let left_f = &self.modules[left.0].functions[left.1];
         let right_f = &self.modules[right.0].functions[right.1];
         right_f
             .number_of_generics()
             .cmp(&left_f.number_of_generics())
+            .then(left.cmp(right))
     }
 
     pub fn find_type(
@@ -133,29 +159,209 @@ impl Parser {
         self_fid: usize,
         ident: &Anot<Identifier, Type>,
     ) -> Result<(usize, usize), ParseFault> {
-        let fid = self.fid_from_path(self_fid, &ident.inner.path);
-        self.modules[fid]
+        let fid = self.fid_from_path(self_fid, &ident.inner.path)?;
+        let found = self.modules[fid]
             .type_ids
             .get(&ident.inner.name)
-            .copied()
-            .map(|tid| (fid, tid))
-            .ok_or_else(|| ParseFault::TypeNotFound(self_fid, ident.clone()))
+            .map(|tid| (fid, *tid));
+        let found = match found {
+            None if ident.inner.path.is_empty() => self.modules[PRELUDE_FID]
+                .type_ids
+                .get(&ident.inner.name)
+                .map(|tid| (PRELUDE_FID, *tid)),
+            found => found,
+        };
+        match found {
+            Some((fid, tid))
+                if !self.is_visible(self_fid, fid, self.modules[fid].types[tid].is_public()) =>
+            {
+                Err(ParseFault::NotExported(ident.clone()))
+            }
+            found => found.ok_or_else(|| ParseFault::TypeNotFound(self_fid, ident.clone())),
+        }
+    }
+
+    // Same lookup as `find_type`, aliases and types can't share a name within a module
+    pub fn find_alias(
+        &self,
+        self_fid: usize,
+        ident: &Anot<Identifier, Type>,
+    ) -> Result<Option<(usize, &Alias)>, ParseFault> {
+        let fid = self.fid_from_path(self_fid, &ident.inner.path)?;
+        let found = match self.modules[fid].aliases.get(&ident.inner.name) {
+            None if ident.inner.path.is_empty() => self.modules[PRELUDE_FID]
+                .aliases
+                .get(&ident.inner.name)
+                .map(|alias| (PRELUDE_FID, alias)),
+            found => found.map(|alias| (fid, alias)),
+        };
+        match found {
+            Some((fid, alias)) if !self.is_visible(self_fid, fid, alias.public) => {
+                Err(ParseFault::NotExported(ident.clone()))
+            }
+            found => Ok(found),
+        }
+    }
+
+    // For types the language itself knows about, such as `maybe` and `result` for `try`
+    pub fn is_prelude_type(&self, fid: usize, tid: usize, name: &str) -> bool {
+        fid == PRELUDE_FID && self.modules[PRELUDE_FID].type_ids.get(name) == Some(&tid)
+    }
+
+    // Same as `is_prelude_type` but for traits
+    pub fn is_prelude_trait(&self, r#trait: (usize, usize), name: &str) -> bool {
+        r#trait.0 == PRELUDE_FID
+            && self.modules[PRELUDE_FID].trait_ids.get(name) == Some(&r#trait.1)
+    }
+
+    // Variants are used by name as constructors (`Just 5`), so we need to look through all enums of
+    // the module. Falls back to prelude if no path was given.
+    pub fn find_variant(
+        &self,
+        self_fid: usize,
+        ident: &Anot<Identifier, Type>,
+    ) -> Result<Option<(usize, usize, u16)>, ParseFault> {
+        let fid = self.fid_from_path(self_fid, &ident.inner.path)?;
+        let found = self.modules[fid]
+            .types
+            .iter()
+            .enumerate()
+            .find_map(|(tid, t)| match t {
+                CustomType::Enum(r#enum) => r#enum
+                    .get_variant(&ident.inner.name)
+                    .map(|(tag, _)| (fid, tid, tag)),
+                CustomType::Struct(_) => None,
+            });
+        match found {
+            None if ident.inner.path.is_empty() && self_fid != PRELUDE_FID => {
+                self.find_variant(PRELUDE_FID, ident)
+            }
+            Some((fid, tid, _))
+                if !self.is_visible(self_fid, fid, self.modules[fid].types[tid].is_public()) =>
+            {
+                Err(ParseFault::NotExported(ident.clone()))
+            }
+            found => Ok(found),
+        }
+    }
+
+    // Items without `pub` can only be used from within their own module, while everything in the
+    // prelude is visible from everywhere
+    pub fn is_visible(&self, self_fid: usize, fid: usize, public: bool) -> bool {
+        public || fid == self_fid || fid == PRELUDE_FID
+    }
+
+    pub fn fid_from_path(&self, self_fid: usize, path: &[String]) -> Result<usize, ParseFault> {
+        if path.is_empty() {
+            Ok(self_fid)
+        } else {
+            self.modules[self_fid].get_import(path)
+        }
+    }
+
+    // Generics that couldn't be bound by the call, such as the `a` of an empty list, can't be
+    // checked here.
+    fn check_constraints(
+        &self,
+        fid: usize,
+        func: &FunctionBuilder,
+        generics: &HashMap<u8, Type>,
+    ) -> Result<(), ParseFault> {
+        for (n, ident, _) in func.constraints.iter() {
+            if let Some(t) = generics.get(n) {
+                let r#trait = self.find_trait(fid, ident)?;
+                if !self.implements(r#trait, t) {
+                    return Err(ParseFault::TraitNotImplemented(ident.clone(), t.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn find_trait(
+        &self,
+        self_fid: usize,
+        ident: &Identifier,
+    ) -> Result<(usize, usize), ParseFault> {
+        let fid = self.fid_from_path(self_fid, &ident.path)?;
+        let found = self.modules[fid]
+            .trait_ids
+            .get(&ident.name)
+            .map(|traitid| (fid, *traitid));
+        let found = match found {
+            None if ident.path.is_empty() => self.modules[PRELUDE_FID]
+                .trait_ids
+                .get(&ident.name)
+                .map(|traitid| (PRELUDE_FID, *traitid)),
+            found => found,
+        };
+        found.ok_or_else(|| ParseFault::TraitNotFound(ident.clone()))
+    }
+
+    // Implementations can be declared in any module, for any trait visible from that module
+    fn implements(&self, r#trait: (usize, usize), t: &Type) -> bool {
+        // Everything that can be compared with `==` and `<` is `eq` and `ord`
+        if (self.is_prelude_trait(r#trait, "eq") || self.is_prelude_trait(r#trait, "ord"))
+            && self.comparable(t, t, &mut Vec::new()).is_ok()
+        {
+            return true;
+        }
+        self.modules.iter().enumerate().any(|(fid, module)| {
+            module.impls.iter().any(|(ident, implemented, _)| {
+                // Impls were already checked by `check_declarations`
+                self.find_trait(fid, ident).ok() == Some(r#trait)
+                    && match self.destruct_custom_type(fid, implemented.clone()) {
+                        Ok(implemented) => self.generic_cmp(&mut HashMap::new(), t, &implemented),
+                        Err(_) => false,
+                    }
+            })
+        })
     }
 
-    fn fid_from_path(&self, self_fid: usize, path: &[String]) -> usize {
-        match path.len() {
-            0 => self_fid,
-            1 => self.modules[self_fid].imports[path.last().as_ref().unwrap().as_str()],
-            _ => panic!("ET: Invalid path length"),
+    // Values are compared structurally, which only works if there are no functions inside of them.
+    // `seen` keeps recursive types from being walked forever.
+    pub fn comparable(
+        &self,
+        compared: &Type,
+        t: &Type,
+        seen: &mut Vec<Type>,
+    ) -> Result<(), ParseFault> {
+        match t {
+            Type::Function(_) => Err(ParseFault::ComparingFunctions(compared.clone())),
+            Type::List(box inner) => self.comparable(compared, inner, seen),
+            Type::Tuple(entries) => entries
+                .iter()
+                .try_for_each(|entry| self.comparable(compared, entry, seen)),
+            Type::KnownCustom(fid, tid, args) => {
+                if seen.contains(t) {
+                    return Ok(());
+                }
+                seen.push(t.clone());
+                let custom = &self.modules[*fid].types[*tid];
+                let generics = custom.generics_for(args);
+                let fields: Vec<&Type> = match custom {
+                    CustomType::Struct(s) => s.fields.iter().map(|(_, t)| t).collect(),
+                    CustomType::Enum(e) => e.fields.iter().flat_map(|(_, ts)| ts).collect(),
+                };
+                for field in fields {
+                    let field = self
+                        .destruct_custom_type(*fid, field.clone())?
+                        .decoded(&generics);
+                    self.comparable(compared, &field, seen)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
         }
     }
 
-    fn are_compatible(&self, want: &[MaybeType], got: &[Type]) -> bool {
+    // Returns the generics bound by the parameters if they fit
+    fn compatible_generics(&self, want: &[MaybeType], got: &[Type]) -> Option<HashMap<u8, Type>> {
         if want.len() != got.len() {
-            return false;
+            return None;
         }
         let mut generics: HashMap<u8, Type> = HashMap::new();
-        want.iter().zip(got.iter()).all(|(want, got)| match want {
+        let fits = want.iter().zip(got.iter()).all(|(want, got)| match want {
             MaybeType::Infer(t) => {
                 if let Some(existing) = t.borrow().as_ref() {
                     return self.generic_cmp(&mut generics, existing, got);
@@ -168,47 +374,64 @@ impl Parser {
                 true
             }
             MaybeType::Known(existing) => self.generic_cmp(&mut generics, existing, got),
-        })
+        });
+        if fits {
+            Some(generics)
+        } else {
+            None
+        }
     }
 
-    fn generic_cmp(&self, generics: &mut HashMap<u8, Type>, left: &Type, right: &Type) -> bool {
-        match &right {
-            Type::Custom(ident) => {
+    // Compares a type of a value (left) with a type that may contain generics (right), binding the
+    // generics as they're encountered.
+    pub fn generic_cmp(&self, generics: &mut HashMap<u8, Type>, left: &Type, right: &Type) -> bool {
+        match (left, right) {
+            (_, Type::Custom(ident)) => {
                 panic!("Un-Deserialized custom type in comparison: {}", ident);
             }
-            Type::Generic(n) => {
+            (Type::Never, _) => true,
+            (_, Type::Generic(n)) => {
                 if let Some(existing) = generics.get(n) {
-                    existing == left
+                    existing.fits(left)
                 } else {
-                    generics.insert(*n, left.clone());
+                    if !matches!(left, Type::Generic(_)) {
+                        generics.insert(*n, left.clone());
+                    }
                     true
                 }
             }
-            Type::List(box Type::Generic(n)) => {
-                if let Some(existing) = generics.get(n) {
-                    Type::List(Box::new(existing.clone())) == *left
-                } else if let Type::List(box left_inner_t) = left {
-                    generics.insert(*n, left_inner_t.clone());
-                    true
-                } else {
-                    false
-                }
+            // Type arguments that couldn't be inferred, such as the `a` of `None` in `maybe<a>`
+            (Type::Generic(_), _) => true,
+            (Type::List(left_inner), Type::List(right_inner)) => {
+                self.generic_cmp(generics, left_inner, right_inner)
             }
-            Type::Function(box (takes, gives)) => {
-                if let Type::Function(box (left_takes, left_gives)) = left {
-                    if takes.len() != left_takes.len() {
-                        return false;
-                    }
-                    let params_ok = takes.iter().enumerate().all(|(i, inner_right)| {
-                        self.generic_cmp(generics, &left_takes[i], inner_right)
-                    });
-                    if !params_ok {
-                        return false;
-                    }
-                    self.generic_cmp(generics, left_gives, gives)
-                } else {
-                    false
+            (Type::Tuple(left_entries), Type::Tuple(entries)) => {
+                left_entries.len() == entries.len()
+                    && left_entries
+                        .iter()
+                        .zip(entries.iter())
+                        .all(|(left_entry, entry)| self.generic_cmp(generics, left_entry, entry))
+            }
+            (Type::Function(box (left_takes, left_gives)), Type::Function(box (takes, gives))) => {
+                if takes.len() != left_takes.len() {
+                    return false;
                 }
+                let params_ok = takes.iter().enumerate().all(|(i, inner_right)| {
+                    self.generic_cmp(generics, &left_takes[i], inner_right)
+                });
+                if !params_ok {
+                    return false;
+                }
+                self.generic_cmp(generics, left_gives, gives)
+            }
+            (Type::KnownCustom(lfid, ltid, left_args), Type::KnownCustom(fid, tid, args)) => {
+                lfid == fid
+                    && ltid == tid
+                    && left_args.len() == args.len()
+                    && left_args
+                        .iter()
+                        .zip(args.iter())
+                        .all(|(left_arg, arg)| self.generic_cmp(generics, left_arg, arg))
             }
             _ => *right == *left,
         }
@@ -220,12 +443,12 @@ impl Parser {
         fid: usize,
         variants: &mut Variants,
         from: &HashMap<Vec<Type>, usize>,
-    ) {
-        from.iter().for_each(|(params, funcid)| {
+    ) -> Result<(), ParseFault> {
+        for (params, funcid) in from.iter() {
             let params = params
                 .iter()
                 .map(|t| self.destruct_custom_type(fid, t.clone()))
-                .collect();
+                .collect::<Result<_, _>>()?;
             match variants.matching.iter_mut().find(|(id, _)| fid == *id) {
                 Some((_, variants_this_module)) => {
                     // Modify existing module
@@ -236,7 +459,8 @@ impl Parser {
                     variants.matching.push((fid, vec![(params, *funcid)]));
                 }
             };
-        });
+        }
+        Ok(())
     }
 }
 
@@ -253,10 +477,13 @@ impl Variants {
         });
     }
 
-    fn keeping(&self, mut predicate: impl FnMut(usize, &[Type]) -> bool) -> Vec<(usize, usize)> {
+    fn keeping(
+        &self,
+        mut predicate: impl FnMut(usize, usize, &[Type]) -> bool,
+    ) -> Vec<(usize, usize)> {
         let mut matches = Vec::new();
         self.for_each(|fid, funcid, types| {
-            if predicate(fid, types) {
+            if predicate(fid, funcid, types) {
                 matches.push((fid, funcid))
             }
         });
@@ -284,8 +511,27 @@ fn infer_all(params: &[MaybeType], known: &[Type]) {
 
 fn swap_generic(t: &mut Type, generics: &HashMap<u8, Type>) {
     match t {
-        Type::Generic(n) => *t = generics[n].clone(),
+        Type::Generic(n) => {
+            if let Some(known) = generics.get(n) {
+                *t = known.clone()
+            }
+        }
+        Type::KnownCustom(_, _, args) => {
+            for t in args {
+                swap_generic(t, generics);
+            }
+        }
+        Type::Custom(ident) => {
+            for t in ident.anot.iter_mut() {
+                swap_generic(t, generics);
+            }
+        }
         Type::List(inner) => swap_generic(inner, generics),
+        Type::Tuple(entries) => {
+            for t in entries {
+                swap_generic(t, generics);
+            }
+        }
         Type::Function(box (takes, gives)) => {
             swap_generic(gives, generics);
             for t in takes {
@@ -311,7 +557,26 @@ fn find_generics<'a>(matching: &[Type], got: &[MaybeType]) -> Result<HashMap<u8,
 
 fn find_generic(t: &Type, got: &MaybeType) -> Option<Vec<(u8, Type)>> {
     match t {
-        Type::Generic(n) => Some(vec![(*n, got.clone().unwrap())]),
+        Type::Generic(n) => match got.clone().unwrap() {
+            // Wasn't inferred at the call site so can't be used to infer this one
+            Type::Generic(_) => None,
+            got => Some(vec![(*n, got)]),
+        },
+        Type::KnownCustom(_, _, args) => {
+            if let MaybeType::Known(Type::KnownCustom(_, _, got_args)) = &got {
+                let mut buf = Vec::new();
+                for (arg, got_arg) in args.iter().zip(got_args.iter()) {
+                    if let Some(mut generics) =
+                        find_generic(arg, &MaybeType::Known(got_arg.clone()))
+                    {
+                        buf.append(&mut generics);
+                    }
+                }
+                Some(buf)
+            } else {
+                None
+            }
+        }
         Type::List(inner) => {
             if let MaybeType::Known(Type::List(got_inner)) = &got {
                 let got_inner: Type = (**got_inner).clone();
@@ -320,6 +585,19 @@ fn find_generic(t: &Type, got: &MaybeType) -> Option<Vec<(u8, Type)>> {
                 unreachable!();
             }
         }
+        Type::Tuple(entries) => {
+            if let Type::Tuple(got_entries) = got.clone().unwrap() {
+                let mut buf = Vec::new();
+                for (entry, got_entry) in entries.iter().zip(got_entries) {
+                    if let Some(mut generics) = find_generic(entry, &MaybeType::Known(got_entry)) {
+                        buf.append(&mut generics);
+                    }
+                }
+                Some(buf)
+            } else {
+                None
+            }
+        }
         Type::Function(box (takes, gives)) => {
             if let MaybeType::Known(Type::Function(box (got_takes, got_gives))) = &got {
                 let mut buf = Vec::new();
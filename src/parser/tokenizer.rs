@@ -1,5 +1,5 @@
 mod token;
-use super::{Tracked, NAME_CHARS};
+use super::{Anot, Identifier, ParseError, ParseFault, Tracked, NAME_CHARS, OP_CHARS};
 use std::convert::TryFrom;
 use std::iter::Peekable;
 pub use token::{Capture, Header, Inlinable, Key, Operator, RawToken, Token};
@@ -9,9 +9,14 @@ pub use token::{Capture, Header, Inlinable, Key, Operator, RawToken, Token};
 pub struct Tokenizer<I: Iterator<Item = char>> {
     source_code: Peekable<I>,
     pub position: usize,
+    // The character walked past most recently, to tell `f -4` apart from `x-4`
+    previous: char,
 
     // For the peek implementation
     pending: Vec<Tracked<RawToken>>,
+
+    // Set by a malformed literal, which also ends the token stream
+    pub fault: Option<ParseError>,
 }
 
 const DEFAULT_STOPPERS: &[char] = &[' ', ',', '(', ')', '[', ']', '\n', '#', '{', '}', '\\'];
@@ -24,6 +29,8 @@ impl<I: Iterator<Item = char>> From<Peekable<I>> for Tokenizer<I> {
             source_code,
             pending: Vec::new(),
             position: 0,
+            previous: '\n',
+            fault: None,
         }
     }
 }
@@ -49,26 +56,21 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
             }
         }
     }
-    pub fn gather_to_recursive(&mut self, predicate: impl Fn(char) -> (bool, bool)) -> String {
+    // Gathers up until the `>` closing an annotation, including any nested annotations
+    fn gather_annotation(&mut self) -> String {
         let mut buf = String::new();
-        loop {
-            let c = self.source_code.peek().copied();
+        let mut depth = 0;
+        while let Some(c) = self.source_code.peek().copied() {
             match c {
-                None => return buf,
-                Some(c) => {
-                    let r = predicate(c);
-                    if r.0 {
-                        return buf;
-                    } else if r.1 {
-                        self.walk();
-                        return self.gather_to_recursive(predicate);
-                    } else {
-                        buf.push(c);
-                        self.walk();
-                    }
-                }
+                '>' if depth == 0 => break,
+                '>' => depth -= 1,
+                '<' => depth += 1,
+                _ => {}
             }
+            buf.push(c);
+            self.walk();
         }
+        buf
     }
     pub fn skip_tokens_until(&mut self, predicate: impl Fn(&RawToken) -> bool) {
         loop {
@@ -84,14 +86,35 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
         }
     }
 
+    // `(a: num)` can only be told apart from a function type such as `(a -> a)` by the colon
+    pub fn peek_second(&mut self) -> Option<&Token> {
+        let first = self.next()?;
+        match self.next() {
+            Some(second) => {
+                self.pending.push(second);
+                self.pending.push(first);
+                self.pending.get(self.pending.len() - 2)
+            }
+            None => {
+                self.pending.push(first);
+                None
+            }
+        }
+    }
+
     fn walk(&mut self) -> Option<char> {
         self.position += 1;
-        self.source_code.next()
+        let c = self.source_code.next();
+        if let Some(c) = c {
+            self.previous = c;
+        }
+        c
     }
 
     fn gather_to(&mut self, stoppers: &[char]) -> (char, String) {
         let mut buf = String::new();
         self.skip_until(|c| c != ' ');
+        let after_space = self.previous.is_whitespace();
         loop {
             let c = match self.source_code.peek() {
                 None => return (0 as char, buf),
@@ -101,6 +124,9 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
                 buf.push(*c);
                 return (self.walk().unwrap(), buf);
             }
+            if buf.is_empty() && *c == '"' {
+                return self.string_literal();
+            }
             match c {
                 '<' => {
                     // Hack to make annotations parse correctly
@@ -108,11 +134,7 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
                         if NAME_CHARS.contains(last) {
                             self.walk();
                             // It's an annotation to previous
-                            let complete_anot = self.gather_to_recursive(|c| match c {
-                                '<' => (false, true),
-                                '>' => (true, false),
-                                _ => (false, false),
-                            });
+                            let complete_anot = self.gather_annotation();
                             buf.push('<');
                             buf.push_str(&complete_anot);
                             buf.push('>');
@@ -146,7 +168,6 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
                             if stoppers.contains(&'/') {
                                 return ('/', buf);
                             } else {
-                                self.walk();
                                 buf.push('/');
                             }
                         }
@@ -155,14 +176,78 @@ impl<I: Iterator<Item = char>> Tokenizer<I> {
                 _ => {
                     if stoppers.contains(&c) {
                         return (*c, buf);
-                    } else {
-                        buf.push(self.walk().unwrap());
                     }
+                    // `-x` and `!b` are a prefix operator followed by its operand. Although `f -4`
+                    // passes a negative literal, since that's what `-` before a digit reads as.
+                    let negative_literal = after_space && buf == "-" && c.is_ascii_digit();
+                    if !OP_CHARS.contains(*c)
+                        && !buf.is_empty()
+                        && buf.chars().all(|b| OP_CHARS.contains(b))
+                        && !negative_literal
+                    {
+                        return (*c, buf);
+                    }
+                    buf.push(self.walk().unwrap());
                 }
             }
         }
     }
 
+    // String literals are gathered as-is including their quotes, since they may contain stoppers
+    // such as spaces. Escape sequences are resolved later by Inlinable.
+    fn string_literal(&mut self) -> (char, String) {
+        let source = self.position;
+        let mut buf = String::new();
+        buf.push(self.walk().unwrap());
+        loop {
+            let c = match self.walk() {
+                None => return self.unmatched_quote(source),
+                Some(c) => c,
+            };
+            buf.push(c);
+            match c {
+                '\\' => match self.walk() {
+                    None => return self.unmatched_quote(source),
+                    Some(escaped) => buf.push(escaped),
+                },
+                '"' => return ('"', buf),
+                _ => {}
+            }
+        }
+    }
+
+    fn unmatched_quote(&mut self, source: usize) -> (char, String) {
+        self.fault = Some(ParseFault::UnmatchedQuote.into_err(source));
+        (0 as char, String::new())
+    }
+
+    // `p.name.first` is split into `p` `.` `name` `.` `first`
+    fn field_access(&mut self, source: usize, chunk: &str) -> Option<Token> {
+        let mut buf = Vec::new();
+        let mut offset = source;
+        for (i, segment) in chunk.split('.').enumerate() {
+            if i != 0 {
+                buf.push(Tracked::new(RawToken::Key(Key::Dot)).set(offset));
+                offset += 1;
+            }
+            if !segment.is_empty() {
+                // Field names may collide with keywords such as `first`
+                let rt = if i == 0 {
+                    RawToken::try_from(segment).ok()?
+                } else {
+                    RawToken::Identifier(Anot::new(Identifier::try_from(segment).ok()?))
+                };
+                buf.push(Tracked::new(rt).set(offset));
+            }
+            offset += segment.len();
+        }
+        let first = buf.remove(0);
+        while let Some(t) = buf.pop() {
+            self.pending.push(t);
+        }
+        Some(first)
+    }
+
     fn single_line_comment(&mut self) {
         loop {
             let c = self.walk();
@@ -193,15 +278,31 @@ impl<I: Iterator<Item = char>> Iterator for Tokenizer<I> {
             return Some(t);
         }
         let source = self.position;
-        let (brk, chunk) = self.gather_to(DEFAULT_STOPPERS);
+        let (brk, mut chunk) = self.gather_to(DEFAULT_STOPPERS);
         if brk == 0 as char && chunk.is_empty() {
             return None;
         }
+
+        // `Just v:` in match arms, the colon isn't part of the identifier
+        if chunk.len() > 1 && chunk.ends_with(':') {
+            chunk.pop();
+            let colon = Tracked::new(RawToken::Key(Key::Colon)).set(source + chunk.len());
+            self.pending.push(colon);
+        }
+
         match RawToken::try_from(chunk.as_str()) {
             Ok(rt) => {
                 self.skip_until(|c| c != ' ');
                 Some(Tracked::new(rt).set(source))
             }
+            Err(fault) if chunk.starts_with('"') => {
+                self.fault = Some(fault.into_err(source));
+                None
+            }
+            Err(_) if chunk.contains('.') => {
+                self.skip_until(|c| c != ' ');
+                self.field_access(source, &chunk)
+            }
             Err(_) => None,
         }
     }
@@ -232,6 +333,9 @@ mod tests {
     fn num(n: i64) -> RawToken {
         RawToken::Inlined(Inlinable::Int(n))
     }
+    fn string(s: &str) -> RawToken {
+        RawToken::Inlined(Inlinable::String(s.to_owned()))
+    }
     fn func(path: &str) -> RawToken {
         ident(path)
     }
@@ -247,6 +351,31 @@ mod tests {
         let result = test("4 + 4 + 4");
         assert_eq!(result, vec![num(4), oper("+"), num(4), oper("+"), num(4)]);
     }
+    #[test]
+    fn division() {
+        let result = test("4 / 2 // comment");
+        assert_eq!(result, vec![num(4), oper("/"), num(2)]);
+    }
+
+    #[test]
+    fn prefix_operator() {
+        let result = test("-x + !b -4 (-4)");
+        assert_eq!(
+            result,
+            vec![
+                oper("-"),
+                ident("x"),
+                oper("+"),
+                oper("!"),
+                ident("b"),
+                num(-4),
+                RawToken::Key(Key::ParenOpen),
+                oper("-"),
+                num(4),
+                RawToken::Key(Key::ParenClose),
+            ]
+        );
+    }
 
     #[test]
     fn function() {
@@ -333,4 +462,110 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn string_literal() {
+        let result = test("io:puts \"hello, (world)\" <> \"tab\\t \\\"quoted\\\"\"");
+        assert_eq!(
+            result,
+            vec![
+                func("io:puts"),
+                string("hello, (world)"),
+                oper("<>"),
+                string("tab\t \"quoted\""),
+            ]
+        )
+    }
+
+    #[test]
+    fn malformed_string_literal() {
+        for code in ["x \"unmatched", "x \"unknown \\q escape\""].iter() {
+            let mut tokenizer = Tokenizer::from(code.chars().peekable());
+            assert_eq!(tokenizer.next().map(|t| t.inner), Some(ident("x")));
+            assert!(tokenizer.next().is_none());
+            assert_eq!(tokenizer.fault.map(|e| e.source_index), Some(2));
+        }
+    }
+
+    #[test]
+    fn field_access() {
+        let result = test("str p.age <> (f x).name.first");
+        assert_eq!(
+            result,
+            vec![
+                ident("str"),
+                ident("p"),
+                RawToken::Key(Key::Dot),
+                ident("age"),
+                oper("<>"),
+                RawToken::Key(Key::ParenOpen),
+                ident("f"),
+                ident("x"),
+                RawToken::Key(Key::ParenClose),
+                RawToken::Key(Key::Dot),
+                ident("name"),
+                RawToken::Key(Key::Dot),
+                ident("first"),
+            ]
+        )
+    }
+
+    #[test]
+    fn match_arms() {
+        let result = test("match x\n | Just v: v\n | _ : 0");
+        assert_eq!(
+            result,
+            vec![
+                RawToken::Key(Key::Match),
+                ident("x"),
+                RawToken::NewLine,
+                RawToken::Key(Key::Bar),
+                ident("Just"),
+                ident("v"),
+                RawToken::Key(Key::Colon),
+                ident("v"),
+                RawToken::NewLine,
+                RawToken::Key(Key::Bar),
+                RawToken::Inlined(Inlinable::Nothing),
+                RawToken::Key(Key::Colon),
+                num(0),
+            ]
+        )
+    }
+
+    #[test]
+    fn nested_annotation() {
+        let result = test("box<maybe<int>> 1");
+        let anot = Anot::from((
+            Identifier::try_from("box").unwrap(),
+            vec![String::from("maybe<int>")],
+        ));
+        assert_eq!(result, vec![RawToken::Identifier(anot), num(1)]);
+    }
+
+    #[test]
+    fn constraints() {
+        let result = test("((a: num, b: eq ord) a b -> a)");
+        assert_eq!(
+            result,
+            vec![
+                RawToken::Key(Key::ParenOpen),
+                RawToken::Key(Key::ParenOpen),
+                ident("a"),
+                RawToken::Key(Key::Colon),
+                ident("num"),
+                RawToken::Key(Key::Comma),
+                ident("b"),
+                RawToken::Key(Key::Colon),
+                ident("eq"),
+                ident("ord"),
+                RawToken::Key(Key::ParenClose),
+                ident("a"),
+                ident("b"),
+                RawToken::Key(Key::Arrow),
+                ident("a"),
+                RawToken::Key(Key::ParenClose),
+            ]
+        )
+    }
 }
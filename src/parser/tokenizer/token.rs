@@ -23,6 +23,9 @@ impl TryFrom<&str> for RawToken {
                 IdentSource::TypeName,
             ));
         }
+        if bytes.starts_with('"') {
+            return Inlinable::string(bytes).map(RawToken::Inlined);
+        }
         if let Ok(t) = Header::try_from(bytes) {
             Ok(RawToken::Header(t))
         } else if let Ok(t) = Key::try_from(bytes) {
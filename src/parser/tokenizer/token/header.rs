@@ -8,6 +8,10 @@ pub enum Header {
     Enum,
     Type,
     Use,
+    Trait,
+    Impl,
+    Alias,
+    Pub,
 }
 
 impl TryFrom<&str> for Header {
@@ -20,6 +24,10 @@ impl TryFrom<&str> for Header {
             "use" => Header::Use,
             "enum" => Header::Enum,
             "operator" => Header::Operator,
+            "trait" => Header::Trait,
+            "impl" => Header::Impl,
+            "alias" => Header::Alias,
+            "pub" => Header::Pub,
             _ => return Err(()),
         };
         Ok(res)
@@ -34,6 +42,10 @@ impl Header {
             Header::Enum => "enum",
             Header::Type => "type",
             Header::Use => "use",
+            Header::Trait => "trait",
+            Header::Impl => "impl",
+            Header::Alias => "alias",
+            Header::Pub => "pub",
         }
     }
 }
@@ -1,4 +1,5 @@
 use crate::ir::Value;
+use crate::parser::ParseFault;
 use std::convert::TryFrom;
 use std::fmt;
 
@@ -7,6 +8,7 @@ pub enum Inlinable {
     Int(i64),
     Float(f64),
     Bool(bool),
+    String(String),
     Nothing,
 }
 
@@ -26,23 +28,53 @@ impl TryFrom<&str> for Inlinable {
         if bytes == "_" {
             return Ok(Inlinable::Nothing);
         }
-        let first = bytes.chars().next();
-        if first == Some('"') {
-            unimplemented!("string literals");
-        }
-        if first == Some('\'') {
+        if bytes.starts_with('\'') {
             unimplemented!("byte literals");
         }
         Err(())
     }
 }
 
+impl Inlinable {
+    // The tokenizer hands us the literal exactly as written, including the surrounding quotes and
+    // any escape sequences. So we need to resolve them here.
+    pub fn string(literal: &str) -> Result<Inlinable, ParseFault> {
+        if literal.len() < 2 || !literal.ends_with('"') {
+            return Err(ParseFault::UnmatchedQuote);
+        }
+        unescape(&literal[1..literal.len() - 1]).map(Inlinable::String)
+    }
+}
+
+fn unescape(raw: &str) -> Result<String, ParseFault> {
+    let mut buf = String::with_capacity(raw.len());
+    let mut iter = raw.chars();
+    while let Some(c) = iter.next() {
+        if c != '\\' {
+            buf.push(c);
+            continue;
+        }
+        match iter.next() {
+            Some('n') => buf.push('\n'),
+            Some('t') => buf.push('\t'),
+            Some('r') => buf.push('\r'),
+            Some('0') => buf.push('\0'),
+            Some('\\') => buf.push('\\'),
+            Some('"') => buf.push('"'),
+            Some(other) => return Err(ParseFault::UnknownEscape(other)),
+            None => return Err(ParseFault::UnmatchedQuote),
+        }
+    }
+    Ok(buf)
+}
+
 impl fmt::Display for Inlinable {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Inlinable::Int(n) => write!(f, "{}", n),
             Inlinable::Float(n) => write!(f, "{}", n),
             Inlinable::Bool(b) => write!(f, "{}", if *b { "true" } else { "false" }),
+            Inlinable::String(s) => write!(f, "{:?}", s),
             Inlinable::Nothing => f.write_str("_"),
         }
     }
@@ -60,6 +92,7 @@ impl From<Inlinable> for Value {
             Inlinable::Float(n) => Value::Float(n),
             Inlinable::Nothing => Value::Nothing,
             Inlinable::Bool(b) => Value::Bool(b),
+            Inlinable::String(s) => Value::String(s.into()),
         }
     }
 }
@@ -24,6 +24,9 @@ pub enum Key {
     Or,
     Dot,
     First,
+    Try,
+    Let,
+    In,
     Colon,
     Where,
     PrimitiveExit,
@@ -59,6 +62,9 @@ impl TryFrom<&str> for Key {
             "or" => Key::Or,
             "and" => Key::And,
             "first" => Key::First,
+            "try" => Key::Try,
+            "let" => Key::Let,
+            "in" => Key::In,
             "???" => Key::PrimitiveUnimplemented,
             _ => return Err(()),
         };
@@ -91,6 +97,9 @@ impl Key {
             Key::And => "and",
             Key::Or => "or",
             Key::First => "first",
+            Key::Try => "try",
+            Key::Let => "let",
+            Key::In => "in",
             Key::Where => "where",
             Key::PrimitiveExit => "exit",
             Key::PrimitiveUnimplemented => "unimplemented",
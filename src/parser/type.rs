@@ -1,5 +1,5 @@
-use super::ParseFault;
 use super::{Anot, Identifier, IdentifierType, Inlinable};
+use super::{ParseError, ParseFault};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -7,6 +7,8 @@ use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
+pub mod alias;
+pub use alias::Alias;
 pub mod r#enum;
 pub use r#enum::Enum;
 pub mod r#struct;
@@ -18,8 +20,11 @@ pub enum Type {
     Int,
     Float,
     Bool,
+    String,
     Generic(u8),
     List(Box<Type>),
+    // `(int, string)`, anonymous records identified by their entries alone
+    Tuple(Vec<Type>),
     Struct(i32, i32),
     Function(Box<(Vec<Type>, Type)>),
 
@@ -47,13 +52,60 @@ pub enum Type {
     // Although how we're gonna get the fid in here I'm not quite sure. Might just need to change
     // TryFrom<&str> to TryFrom<(usize, &str)>.
     Custom(Anot<Identifier, Type>),
-    KnownCustom(usize, usize),
+    // The type arguments are kept in the order the generics were declared in, `maybe<int>` for
+    // `enum maybe<a>` is `KnownCustom(fid, tid, [Int])`.
+    KnownCustom(usize, usize, Vec<Type>),
+    // The type of expressions that never produce a value, such as `exit`. It fits wherever a
+    // value of any other type is expected.
+    Never,
 }
 
 pub enum CustomType {
     Struct(Struct),
     Enum(Enum),
 }
+
+// The name of a type or alias being declared, whose type parameters can only be generics such as
+// the `a` of `type box<a>`
+pub fn declared_ident(
+    ident: Anot<Identifier, String>,
+    pos: usize,
+) -> Result<Anot<Identifier, Type>, ParseError> {
+    let ident = ident
+        .try_map_anot(|s| Type::try_from(s.as_str()))
+        .map_err(|e| e.into_err(pos))?;
+    match ident.anot.iter().find(|t| !matches!(t, Type::Generic(_))) {
+        Some(t) => Err(ParseFault::TypeParamNotGeneric(t.clone()).into_err(pos)),
+        None => Ok(ident),
+    }
+}
+impl CustomType {
+    pub fn type_args(&self) -> &[Type] {
+        match self {
+            CustomType::Enum(a) => &a.type_args,
+            CustomType::Struct(a) => &a.type_args,
+        }
+    }
+
+    pub fn is_public(&self) -> bool {
+        match self {
+            CustomType::Enum(a) => a.public,
+            CustomType::Struct(a) => a.public,
+        }
+    }
+
+    // Maps the declared generics to the type arguments of an instantiation of this type
+    pub fn generics_for(&self, args: &[Type]) -> HashMap<u8, Type> {
+        self.type_args()
+            .iter()
+            .zip(args.iter())
+            .filter_map(|(param, arg)| match param {
+                Type::Generic(n) => Some((*n, arg.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+}
 impl fmt::Display for CustomType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -99,6 +151,9 @@ impl Type {
         match self {
             Type::Generic(n) => generics.get(&n).cloned().unwrap_or(Type::Generic(n)),
             Type::List(box t) => Type::List(Box::new(t.decoded(generics))),
+            Type::Tuple(entries) => {
+                Type::Tuple(entries.into_iter().map(|t| t.decoded(generics)).collect())
+            }
             Type::Function(attr) => {
                 // TODO: Clone can be avoided
                 let (mut params, returns) = (attr.0, attr.1);
@@ -107,9 +162,62 @@ impl Type {
                     .for_each(|t| *t = t.clone().decoded(generics));
                 Type::Function(Box::new((params, returns.decoded(generics))))
             }
+            Type::KnownCustom(fid, tid, args) => Type::KnownCustom(
+                fid,
+                tid,
+                args.into_iter().map(|t| t.decoded(generics)).collect(),
+            ),
             _ => self,
         }
     }
+
+    // Used where two values need to agree on a type, such as the branches of an if expression.
+    //
+    // A generic in the type of a value means that it couldn't be inferred, such as the `a` of `None`
+    // for `enum maybe<a>`. Those are filled in by the other side.
+    pub fn merge(&self, other: &Type) -> Option<Type> {
+        match (self, other) {
+            (Type::Never, _) => Some(other.clone()),
+            (_, Type::Never) => Some(self.clone()),
+            (Type::Generic(_), _) => Some(other.clone()),
+            (_, Type::Generic(_)) => Some(self.clone()),
+            (Type::List(left), Type::List(right)) => Some(Type::List(Box::new(left.merge(right)?))),
+            (Type::Tuple(left), Type::Tuple(right)) if left.len() == right.len() => {
+                let entries = left
+                    .iter()
+                    .zip(right.iter())
+                    .map(|(l, r)| l.merge(r))
+                    .collect::<Option<Vec<Type>>>()?;
+                Some(Type::Tuple(entries))
+            }
+            (Type::Function(box (ltakes, lgives)), Type::Function(box (rtakes, rgives)))
+                if ltakes.len() == rtakes.len() =>
+            {
+                let takes = ltakes
+                    .iter()
+                    .zip(rtakes.iter())
+                    .map(|(l, r)| l.merge(r))
+                    .collect::<Option<Vec<Type>>>()?;
+                Some(Type::Function(Box::new((takes, lgives.merge(rgives)?))))
+            }
+            (Type::KnownCustom(lfid, ltid, largs), Type::KnownCustom(rfid, rtid, rargs))
+                if lfid == rfid && ltid == rtid && largs.len() == rargs.len() =>
+            {
+                let args = largs
+                    .iter()
+                    .zip(rargs.iter())
+                    .map(|(l, r)| l.merge(r))
+                    .collect::<Option<Vec<Type>>>()?;
+                Some(Type::KnownCustom(*lfid, *ltid, args))
+            }
+            _ if self == other => Some(self.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn fits(&self, other: &Type) -> bool {
+        self.merge(other).is_some()
+    }
 }
 
 impl std::default::Default for Type {
@@ -131,6 +239,11 @@ impl TryFrom<&str> for Type {
                 let inner = source[1..source.len() - 2].trim();
                 return Ok(Type::List(Box::new(Type::try_from(inner)?)));
             }
+            // Tuples
+            if first == '(' {
+                return tuple(&mut source[1..].chars())
+                    .ok_or_else(|| ParseFault::NotValidType(source.to_string()));
+            }
             // Unbound Generics
             if (first as u8) > 96 && (first as u8) < 123 && source.len() == 1 {
                 return Ok(Type::Generic(first as u8 - 97));
@@ -163,6 +276,7 @@ impl TryFrom<&str> for Type {
             "float" => Type::Float,
             "nothing" | "_" => Type::Nothing,
             "bool" => Type::Bool,
+            "string" => Type::String,
             _ => {
                 // TODO: This tbuf.as_str() causes an unessesarry allocation
                 Type::Custom(Anot::from((Identifier::try_from(tbuf.as_str())?, anot)))
@@ -181,7 +295,7 @@ pub fn splice_to<I: Iterator<Item = char>>(iter: &mut I, points: &str) -> Option
         }
         match c {
             '[' => {
-                if !s.is_empty() {
+                if !s.trim().is_empty() {
                     panic!("ET: Unexpected [");
                 }
                 let (a, t) = splice_to(iter, "]")?;
@@ -189,10 +303,21 @@ pub fn splice_to<I: Iterator<Item = char>>(iter: &mut I, points: &str) -> Option
                 let after = iter.next();
                 return Some((after.unwrap_or(a), Type::List(Box::new(t))));
             }
+            '(' => {
+                if !s.trim().is_empty() {
+                    panic!("ET: Unexpected (");
+                }
+                let t = tuple(iter)?;
+                let after = iter.next();
+                return Some((after.unwrap_or(')'), t));
+            }
             '<' => {
                 let anot = annotation(iter).expect("ET");
+                // Nested annotations such as `box<maybe<int>>` need the outer one to see what's
+                // after the inner `>`
+                let after = iter.next().unwrap_or('>');
                 return Some((
-                    '>',
+                    after,
                     (Type::Custom(Anot::from((
                         Identifier {
                             path: Vec::new(),
@@ -210,6 +335,22 @@ pub fn splice_to<I: Iterator<Item = char>>(iter: &mut I, points: &str) -> Option
     None
 }
 
+// The entries of a tuple type following its `(`, such as `int, [a])`
+fn tuple<I: Iterator<Item = char>>(iter: &mut I) -> Option<Type> {
+    let mut entries = Vec::new();
+    loop {
+        let (was, t) = splice_to(iter, ",)")?;
+        entries.push(t);
+        if was == ')' {
+            return if entries.len() > 1 {
+                Some(Type::Tuple(entries))
+            } else {
+                None
+            };
+        }
+    }
+}
+
 pub fn annotation<I: Iterator<Item = char>>(iter: &mut I) -> Option<Vec<Type>> {
     let mut annotations = Vec::new();
     loop {
@@ -220,9 +361,6 @@ pub fn annotation<I: Iterator<Item = char>>(iter: &mut I) -> Option<Vec<Type>> {
                 }
                 '>' => {
                     annotations.push(t);
-                    if let Some(c) = iter.next() {
-                        panic!("ET: Unexpected {}", c);
-                    }
                     return Some(annotations);
                 }
                 _ => unreachable!(),
@@ -244,6 +382,7 @@ impl From<&Inlinable> for Type {
             Inlinable::Int(_) => Type::Int,
             Inlinable::Float(_) => Type::Float,
             Inlinable::Bool(_) => Type::Bool,
+            Inlinable::String(_) => Type::String,
             Inlinable::Nothing => Type::Nothing,
         }
     }
@@ -265,9 +404,11 @@ impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Type::Nothing => f.write_str("nothing"),
+            Type::Never => f.write_str("never"),
             Type::Int => f.write_str("int"),
             Type::Float => f.write_str("float"),
             Type::Bool => f.write_str("bool"),
+            Type::String => f.write_str("string"),
             Type::Generic(gid) => write!(f, "{}", (gid + 97) as char),
             Type::Function(box (takes, gives)) => write!(
                 f,
@@ -280,9 +421,30 @@ impl fmt::Display for Type {
                 gives
             ),
             Type::List(inner) => write!(f, "[{}]", inner.to_string()),
+            Type::Tuple(entries) => write!(
+                f,
+                "({})",
+                entries
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
             Type::Struct(fid, tid) => write!(f, "Struct({}:{})", fid, tid),
-            Type::Custom(name) => write!(f, "unevaluated type {}", name),
-            Type::KnownCustom(fid, name) => write!(f, "{}:{}", fid, name),
+            Type::Custom(name) => write!(f, "{}", name),
+            Type::KnownCustom(fid, name, args) if args.is_empty() => {
+                write!(f, "{}:{}", fid, name)
+            }
+            Type::KnownCustom(fid, name, args) => write!(
+                f,
+                "{}:{}<{}>",
+                fid,
+                name,
+                args.iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
         }
     }
 }
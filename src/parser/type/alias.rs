@@ -0,0 +1,57 @@
+use super::Type;
+use crate::parser::{
+    tokenizer::TokenSource, Anot, FunctionBuilder, Identifier, ParseError, ParseFault, RawToken,
+    Tokenizer,
+};
+
+// Aliases aren't types of their own, they're replaced by the aliased type once destructed
+pub struct Alias {
+    pub aliased: Type,
+
+    // The generics declared with the alias, `alias pair<a> = (a, a)` has `[Generic(0)]`
+    pub type_args: Vec<Type>,
+
+    // Where the aliased type was written
+    pub pos: usize,
+
+    pub public: bool,
+}
+
+// `alias handler = (int -> int)`
+pub fn parse<I: Iterator<Item = char>>(
+    tokenizer: &mut Tokenizer<I>,
+) -> Result<(Anot<Identifier, Type>, Type, usize), ParseError> {
+    let (ident, ident_pos) = match tokenizer.next().map(|t| t.sep()) {
+        Some((RawToken::Identifier(ident), pos)) => (ident, pos),
+        Some((other, pos)) => {
+            return ParseFault::GotButExpected(other, vec!["alias name".into()])
+                .into_err(pos)
+                .into()
+        }
+        None => {
+            return ParseFault::EndedWhileExpecting(vec!["alias name".into()])
+                .into_err(tokenizer.position - 1)
+                .into()
+        }
+    };
+    match tokenizer.next().map(|t| t.sep()) {
+        Some((RawToken::Identifier(eq), _)) if eq.inner.name == "=" => {}
+        Some((other, pos)) => {
+            return ParseFault::GotButExpected(other, vec!["=".into()])
+                .into_err(pos)
+                .into()
+        }
+        None => {
+            return ParseFault::EndedWhileExpecting(vec!["=".into()])
+                .into_err(tokenizer.position - 1)
+                .into()
+        }
+    }
+    let aliased_pos = tokenizer
+        .peek()
+        .map(|t| t.pos())
+        .unwrap_or(tokenizer.position);
+    let aliased = FunctionBuilder::new().parse_type(tokenizer)?;
+    let ident = super::declared_ident(ident, ident_pos)?;
+    Ok((ident, aliased, aliased_pos))
+}
@@ -1,18 +1,38 @@
 use super::Type;
-use crate::parser::{tokenizer::TokenSource, Anot, Identifier, ParseError, RawToken, Tokenizer};
-use std::collections::HashMap;
+use crate::parser::{
+    tokenizer::TokenSource, Anot, Identifier, ParseError, ParseFault, RawToken, Tokenizer,
+};
 use std::convert::TryFrom;
 use std::fmt;
 
 pub struct Enum {
-    pub fields: HashMap<String, Vec<Type>>,
+    // Variants are kept in declaration order since their index is used as the runtime tag
+    pub fields: Vec<(String, Vec<Type>)>,
+    // Where each variant was declared, indexed the same as `fields`
+    pub positions: Vec<usize>,
 
-    pub type_args: Vec<()>,
+    // The generics declared with the type, `enum maybe<a>` has `[Generic(0)]`
+    pub type_args: Vec<Type>,
+
+    pub public: bool,
+}
+
+impl Enum {
+    pub fn get_variant(&self, name: &str) -> Option<(u16, &[Type])> {
+        self.fields
+            .iter()
+            .enumerate()
+            .find(|(_, (n, _))| n == name)
+            .map(|(tag, (_, payload))| (tag as u16, payload.as_slice()))
+    }
 }
 
+// A variant along with its payload and where it was declared
+pub type Variant = (String, Vec<Type>, usize);
+
 pub fn parse<I: Iterator<Item = char>>(
     tokenizer: &mut Tokenizer<I>,
-) -> Result<(Anot<Identifier, Type>, HashMap<String, Vec<Type>>), ParseError> {
+) -> Result<(Anot<Identifier, Type>, Vec<Variant>), ParseError> {
     let first = tokenizer.next().ok_or_else(|| panic!("ET"))?;
     let type_ident_pos = first.pos();
     let type_ident = if let RawToken::Identifier(ident) = first.inner {
@@ -31,18 +51,19 @@ pub fn parse<I: Iterator<Item = char>>(
         );
     }
 
-    let mut fields = HashMap::new();
+    let mut fields: Vec<Variant> = Vec::new();
     loop {
         match parse_field(tokenizer)? {
-            Some((name, type_arguments)) => {
-                if fields.insert(name, type_arguments).is_some() {
-                    panic!("ET: Duplicates of field");
+            Some((name, type_arguments, pos)) => {
+                if fields.iter().any(|(n, _, _)| *n == name) {
+                    return ParseFault::VariantAlreadyDeclared(name)
+                        .into_err(pos)
+                        .into();
                 }
+                fields.push((name, type_arguments, pos));
             }
             None => {
-                let type_ident = type_ident
-                    .try_map_anot(|s| Type::try_from(s.as_str()))
-                    .map_err(|e| e.into_err(type_ident_pos))?;
+                let type_ident = super::declared_ident(type_ident, type_ident_pos)?;
                 return Ok((type_ident, fields));
             }
         }
@@ -51,14 +72,18 @@ pub fn parse<I: Iterator<Item = char>>(
 
 fn parse_field<I: Iterator<Item = char>>(
     tokenizer: &mut Tokenizer<I>,
-) -> Result<Option<(String, Vec<Type>)>, ParseError> {
+) -> Result<Option<Variant>, ParseError> {
     let first = tokenizer.peek();
 
     match first.map(|a| &a.inner) {
         Some(RawToken::Identifier(_)) => {
-            let (field_name_ident, _field_pos) = assume!(RawToken::Identifier, tokenizer.next());
+            let (field_name_ident, field_pos) = assume!(RawToken::Identifier, tokenizer.next());
             let type_arguments = parse_type_arguments(tokenizer)?;
-            Ok(Some((field_name_ident.inner.name, type_arguments)))
+            Ok(Some((
+                field_name_ident.inner.name,
+                type_arguments,
+                field_pos,
+            )))
         }
         Some(RawToken::NewLine) => {
             tokenizer.next();
@@ -78,7 +103,7 @@ fn parse_type_arguments<I: Iterator<Item = char>>(
             Some(RawToken::Header(_)) | None | Some(RawToken::NewLine) => return Ok(buf),
             Some(RawToken::Identifier(_)) => {
                 let (type_param_type_name, pos) = assume!(RawToken::Identifier, tokenizer.next());
-                let t = Type::try_from(type_param_type_name.inner.name.as_str())
+                let t = Type::try_from(type_param_type_name.to_string().as_str())
                     .map_err(|e| e.into_err(pos))?;
                 buf.push(t);
             }
@@ -89,6 +114,16 @@ fn parse_type_arguments<I: Iterator<Item = char>>(
 
 impl fmt::Display for Enum {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<TODO>")
+        if !self.type_args.is_empty() {
+            let args = self.type_args.iter().map(|t| t.to_string());
+            write!(f, "<{}>", args.collect::<Vec<_>>().join(", "))?;
+        }
+        for (name, types) in self.fields.iter() {
+            write!(f, "\n    {}", name)?;
+            for t in types.iter() {
+                write!(f, " {}", t)?;
+            }
+        }
+        Ok(())
     }
 }
@@ -1,53 +1,70 @@
 use super::Type;
 use crate::parser::{
-    ast, tokenizer::TokenSource, Anot, Identifier, ParseError, ParseFault, RawToken, Tokenizer,
-    Tracked,
+    ast, tokenizer::TokenSource, Anot, Fixity, Identifier, ParseError, ParseFault, RawToken,
+    Tokenizer, Tracked,
 };
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 
+// A field along with where its type was written and its default
+type Field = (String, Type, usize, Option<Tracked<ast::Entity>>);
+
 pub struct Struct {
     pub fields: Vec<(String, Type)>,
+    // Indexed the same as `fields`. Defaults are evaluated in the module the type is declared in.
+    pub defaults: Vec<Option<Tracked<ast::Entity>>>,
+    // Where the type of each field was written, also indexed the same as `fields`
+    pub positions: Vec<usize>,
+
+    // The generics declared with the type, `type box<a>` has `[Generic(0)]`
+    pub type_args: Vec<Type>,
 
-    // type_args: Vec<super::Trait>,
-    pub type_args: Vec<()>,
+    pub public: bool,
 }
 
 impl Struct {
-    pub fn verify_record_fields(&self, got: &[(String, Type)]) -> Result<(), ParseFault> {
-        unimplemented!();
-    }
-
     // We don't actually use the names from the struct fields anymore. We actually
     // just rely on the order they're written in
     //
-    // We also grab the type here out of convenience
-    pub fn copy_order_for(
-        &self,
-        other: &[(String, Tracked<ast::Entity>)],
-    ) -> Vec<(Tracked<ast::Entity>, Type)> {
-        let mut actual = Vec::with_capacity(self.fields.len());
-        for (name, t) in self.fields.iter() {
-            actual.push((
-                other
-                    .iter()
-                    .find_map(|(n, e)| if n == name { Some(e.clone()) } else { None })
-                    .unwrap_or_else(|| panic!("TODO: Default missing fields")),
-                t.clone(),
-            ))
+    // We also grab the type here out of convenience. Fields that weren't given fall back to their
+    // default value, which is marked with `true`.
+    pub fn copy_order_for<'a>(
+        &'a self,
+        other: &'a [(String, Tracked<ast::Entity>)],
+    ) -> Result<Vec<(&'a Tracked<ast::Entity>, bool, Type)>, ParseFault> {
+        if let Some((name, _)) = other
+            .iter()
+            .find(|(n, _)| !self.fields.iter().any(|(name, _)| name == n))
+        {
+            return Err(ParseFault::FieldNotFound(
+                name.clone(),
+                self.fields.iter().map(|(name, _)| name.clone()).collect(),
+            ));
         }
-        if actual.len() != other.len() {
-            panic!("ET: This record has a field that doesn't exist on the type");
+        let mut actual = Vec::with_capacity(self.fields.len());
+        for (i, (name, t)) in self.fields.iter().enumerate() {
+            let given = other
+                .iter()
+                .find_map(|(n, e)| if n == name { Some(e) } else { None });
+            match (given, &self.defaults[i]) {
+                (Some(entity), _) => actual.push((entity, false, t.clone())),
+                (None, Some(default)) => actual.push((default, true, t.clone())),
+                (None, None) => return Err(ParseFault::FieldMissing(name.clone())),
+            }
         }
-        actual
+        Ok(actual)
     }
 }
 
 impl fmt::Display for Struct {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "<TODO>")?;
-        for (name, types) in self.fields.iter() {
+        for (i, (name, types)) in self.fields.iter().enumerate() {
             write!(f, "\n    {} {}", name, types,)?;
+            if let Some(default) = &self.defaults[i] {
+                write!(f, " = {}", default)?;
+            }
         }
         Ok(())
     }
@@ -55,7 +72,8 @@ impl fmt::Display for Struct {
 
 pub fn parse<I: Iterator<Item = char>>(
     tokenizer: &mut Tokenizer<I>,
-) -> Result<(Anot<Identifier, Type>, Vec<(String, Type)>), ParseError> {
+    fixities: &HashMap<String, Fixity>,
+) -> Result<(Anot<Identifier, Type>, Vec<Field>), ParseError> {
     let first = tokenizer.next().ok_or_else(|| panic!("ET"))?;
     let type_ident_pos = first.pos();
     let type_ident = if let RawToken::Identifier(ident) = first.inner {
@@ -76,9 +94,9 @@ pub fn parse<I: Iterator<Item = char>>(
 
     let mut fields = Vec::new();
     loop {
-        match parse_field(tokenizer)? {
-            Some((name, t)) => {
-                fields.push((name, t));
+        match parse_field(tokenizer, fixities)? {
+            Some(field) => {
+                fields.push(field);
                 if let Some(a) = tokenizer.next() {
                     let (rt, _pos) = a.sep();
                     if rt != RawToken::NewLine {
@@ -86,16 +104,12 @@ pub fn parse<I: Iterator<Item = char>>(
                     }
                 } else {
                     // This field line was the last in the file
-                    let type_ident = type_ident
-                        .try_map_anot(|s| Type::try_from(s.as_str()))
-                        .map_err(|e| e.into_err(type_ident_pos))?;
+                    let type_ident = super::declared_ident(type_ident, type_ident_pos)?;
                     return Ok((type_ident, fields));
                 }
             }
             None => {
-                let type_ident = type_ident
-                    .try_map_anot(|s| Type::try_from(s.as_str()))
-                    .map_err(|e| e.into_err(type_ident_pos))?;
+                let type_ident = super::declared_ident(type_ident, type_ident_pos)?;
                 return Ok((type_ident, fields));
             }
         }
@@ -104,7 +118,8 @@ pub fn parse<I: Iterator<Item = char>>(
 
 fn parse_field<I: Iterator<Item = char>>(
     tokenizer: &mut Tokenizer<I>,
-) -> Result<Option<(String, Type)>, ParseError> {
+    fixities: &HashMap<String, Fixity>,
+) -> Result<Option<Field>, ParseError> {
     let first = tokenizer.peek();
 
     match first.map(|a| &a.inner) {
@@ -112,18 +127,35 @@ fn parse_field<I: Iterator<Item = char>>(
             let (field_name_ident, _field_pos) = assume!(RawToken::Identifier, tokenizer.next());
             let (second, pos) = tokenizer.next().ok_or_else(|| panic!("ET"))?.sep();
             if let RawToken::Identifier(field_type_ident) = second {
-                let t = Type::try_from(field_type_ident.inner.name.as_str())
+                let t = Type::try_from(field_type_ident.to_string().as_str())
                     .map_err(|e| e.into_err(pos))?;
-                Ok(Some((field_name_ident.inner.name, t)))
+                let default = parse_default(tokenizer, fixities)?;
+                Ok(Some((field_name_ident.inner.name, t, pos, default)))
             } else {
                 panic!("ET {:?} cannot be used as field type", second);
             }
         }
         Some(RawToken::NewLine) => {
             tokenizer.next();
-            parse_field(tokenizer)
+            parse_field(tokenizer, fixities)
         }
         Some(RawToken::Header(_)) | None => Ok(None),
         Some(other) => panic!("ET: Unexpected stuff here: {:?}", other),
     }
 }
+
+// `port int = 8080`
+fn parse_default<I: Iterator<Item = char>>(
+    tokenizer: &mut Tokenizer<I>,
+    fixities: &HashMap<String, Fixity>,
+) -> Result<Option<Tracked<ast::Entity>>, ParseError> {
+    match tokenizer.peek().map(|t| &t.inner) {
+        Some(RawToken::Identifier(ident)) if ident.inner.name == "=" => {
+            tokenizer.next();
+            ast::AstBuilder::single_line(tokenizer, fixities)
+                .run_chunk()
+                .map(Some)
+        }
+        _ => Ok(None),
+    }
+}